@@ -0,0 +1,155 @@
+//! Derive macros for the GNet networking library.
+//!
+//! Use through the `derive` feature of `gnet`, which re-exports them.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, Ident, Type};
+
+/// Derive `ByteSerialize` for a struct or an enum.
+///
+/// Fields are serialized in declaration order using their own `ByteSerialize` implementations.
+/// Enums are serialized as a `u8` discriminant, the index of the variant in declaration order,
+/// followed by the fields of the variant. Unit structs serialize to no bytes.
+#[proc_macro_derive(ByteSerialize)]
+pub fn derive_byte_serialize(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	byte_serialize(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// Fields of a struct or an enum variant, bound to local variables.
+///
+/// The variables have generated names, so that fields do not collide with the locals of the
+/// generated code.
+struct Bindings {
+	/// Pattern destructuring a value into the bindings, or constructing one from them.
+	pattern: TokenStream,
+	idents: Vec<Ident>,
+	types: Vec<Type>,
+}
+
+impl Bindings {
+	fn new(path: TokenStream, fields: &Fields) -> Self {
+		let types = fields.iter().map(|field| field.ty.clone()).collect();
+		let idents: Vec<_> = (0 .. fields.len()).map(|i| format_ident!("__field_{}", i)).collect();
+		match fields {
+			Fields::Named(fields) => {
+				let names = fields.named.iter().map(|field| field.ident.as_ref().unwrap());
+				Self { pattern: quote!(#path { #(#names: #idents),* }), idents, types }
+			},
+			Fields::Unnamed(_) => Self { pattern: quote!(#path ( #(#idents),* )), idents, types },
+			Fields::Unit => Self { pattern: path, idents: Vec::new(), types },
+		}
+	}
+
+	/// Expression summing the byte counts of the bound references.
+	fn sum_byte_counts(&self) -> TokenStream {
+		let idents = &self.idents;
+		quote!(0 #(+ ::gnet::byte::ByteSerialize::byte_count(#idents))*)
+	}
+
+	/// Statements serializing the bound references to `__bytes` starting at `__offset`.
+	fn write_fields(&self) -> TokenStream {
+		let idents = &self.idents;
+		quote!(#(
+			::gnet::byte::ByteSerialize::to_bytes(#idents, &mut __bytes[__offset ..]);
+			__offset += ::gnet::byte::ByteSerialize::byte_count(#idents);
+		)*)
+	}
+
+	/// Statements deserializing the bindings from `__bytes` starting at `__offset`.
+	fn read_fields(&self) -> TokenStream {
+		let idents = &self.idents;
+		let types = &self.types;
+		quote!(#(
+			let (#idents, __byte_count) = <#types as ::gnet::byte::ByteSerialize>::from_bytes(&__bytes[__offset ..])?;
+			__offset += __byte_count;
+		)*)
+	}
+}
+
+fn byte_serialize(mut input: DeriveInput) -> Result<TokenStream, Error> {
+	let (byte_count, to_bytes, from_bytes) = match &input.data {
+		Data::Struct(data) => {
+			let bindings = Bindings::new(quote!(Self), &data.fields);
+			let pattern = &bindings.pattern;
+			let byte_count = bindings.sum_byte_counts();
+			let to_bytes = bindings.write_fields();
+			let from_bytes = bindings.read_fields();
+			(
+				quote!(match self { #pattern => #byte_count }),
+				quote!(match self { #pattern => { #to_bytes } }),
+				quote!(#from_bytes Ok((#pattern, __offset))),
+			)
+		},
+		Data::Enum(data) => {
+			if data.variants.len() > 256 {
+				return Err(Error::new(Span::call_site(), "ByteSerialize may only be derived for enums of up to 256 variants"))
+			}
+			let mut byte_count_arms = Vec::new();
+			let mut to_bytes_arms = Vec::new();
+			let mut from_bytes_arms = Vec::new();
+			for (index, variant) in data.variants.iter().enumerate() {
+				let index = index as u8;
+				let ident = &variant.ident;
+				let bindings = Bindings::new(quote!(Self::#ident), &variant.fields);
+				let pattern = &bindings.pattern;
+				let byte_count = bindings.sum_byte_counts();
+				let to_bytes = bindings.write_fields();
+				let from_bytes = bindings.read_fields();
+				byte_count_arms.push(quote!(#pattern => 1 + #byte_count));
+				to_bytes_arms.push(quote!(#pattern => {
+					::gnet::byte::ByteSerialize::to_bytes(&#index, __bytes);
+					__offset += 1;
+					#to_bytes
+				}));
+				from_bytes_arms.push(quote!(#index => {
+					#from_bytes
+					Ok((#pattern, __offset))
+				}));
+			}
+			(
+				quote!(match self { #(#byte_count_arms,)* }),
+				quote!(match self { #(#to_bytes_arms)* }),
+				quote!(
+					let (__discriminant, __byte_count) = <u8 as ::gnet::byte::ByteSerialize>::from_bytes(__bytes)?;
+					__offset += __byte_count;
+					match __discriminant {
+						#(#from_bytes_arms)*
+						_ => Err(::gnet::byte::SerializationError::UnexpectedValue),
+					}
+				),
+			)
+		},
+		Data::Union(data) => {
+			return Err(Error::new(data.union_token.span, "ByteSerialize can not be derived for unions"))
+		},
+	};
+
+	for param in input.generics.type_params_mut() {
+		param.bounds.push(parse_quote!(::gnet::byte::ByteSerialize));
+	}
+	let name = &input.ident;
+	let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+	Ok(quote!(
+		impl #impl_generics ::gnet::byte::ByteSerialize for #name #type_generics #where_clause {
+			#[allow(unused_variables)]
+			fn byte_count(&self) -> usize {
+				#byte_count
+			}
+
+			#[allow(unused_variables, unused_mut, unused_assignments)]
+			fn to_bytes(&self, __bytes: &mut [u8]) {
+				let mut __offset = 0;
+				#to_bytes
+			}
+
+			#[allow(unused_variables, unused_mut)]
+			fn from_bytes(__bytes: &[u8]) -> ::std::result::Result<(Self, usize), ::gnet::byte::SerializationError> {
+				let mut __offset = 0;
+				#from_bytes
+			}
+		}
+	))
+}
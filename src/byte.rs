@@ -12,22 +12,35 @@ pub enum SerializationError {
 	UnexpectedValue,
 }
 
-// TODO: custom #[derive(ByteSerialize)]
 /// A trait for objects that can be written to or read from a byte-stream.
 ///
 /// Correct implementations of this trait fulfil following predicates:
 /// - A call to [`to_bytes`](Self::to_bytes) must write no more than
-/// [`byte_count`](Self::byte_count) bytes.
+///   [`byte_count`](Self::byte_count) bytes.
 /// - The byte-stream produced by a call to [`to_bytes`](Self::to_bytes) should produce a valid
-/// object on call of [`from_bytes`](Self::from_bytes).
+///   object on call of [`from_bytes`](Self::from_bytes).
 ///
 /// `ByteSerialize` is implemented by default for:
 /// - Empty type. (`()`)
 /// - Trivial types. (ex: `u8`, `usize`, `float`).
+/// - Strings, prefixed with their `u32` byte count. (`String`).
+/// - Optional `ByteSerialize` objects, prefixed with a discriminant byte. (ex: `Option<u32>`).
+/// - Vectors of `ByteSerialize` objects, prefixed with their `u32` element count. (ex: `Vec<u8>`).
+/// - Fixed-point numbers. ([`Fixed`](Fixed), [`Fixed64`](Fixed64)).
+/// - Normalized numbers. ([`Unorm8`](Unorm8), [`Unorm16`](Unorm16)).
+/// - Arrays of optional `ByteSerialize` objects. ([`OptionArray`](OptionArray)).
+/// - Length-delimited opaque values. ([`Framed`](Framed)).
+/// - Remaining bytes at the end of a message. ([`Tail`](Tail)).
 /// - Arrays of `ByteSerialize + Default` objects up to size 32.
-/// (ex: `[f32; 3]`, `[[f32; 4]; 4]`, `[u8; 4]`).
-/// - Tuples of `ByteSerialize` objects.
-/// (ex: `(f32, f64, u16)`, `([u16; 4], u16)`, `((i32, isize), usize)`).
+///   (ex: `[f32; 3]`, `[[f32; 4]; 4]`, `[u8; 4]`).
+/// - Tuples of `ByteSerialize` objects up to size 16.
+///   (ex: `(f32, f64, u16)`, `([u16; 4], u16)`, `((i32, isize), usize)`).
+///
+/// Structs and enums may derive it with `#[derive(ByteSerialize)]` with the `derive` feature enabled.
+/// Single-field newtypes may implement it using [`byte_serialize_newtype!`](crate::byte_serialize_newtype).
+/// Types generated by the `bitflags` crate may implement it using
+/// `byte_serialize_bitflags!` with the `bitflags` feature enabled.
+/// Types implementing `serde` traits may be wrapped in `SerdeParcel` with the `serde` feature enabled.
 pub trait ByteSerialize: Sized {
 	/// Size of the serialization of the object in bytes.
 	fn byte_count(&self) -> usize;
@@ -46,7 +59,54 @@ pub trait ByteSerialize: Sized {
 	fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), SerializationError>;
 }
 
+/// A trait for objects that can be written to a byte-stream without being owned.
+///
+/// Allows writing composite objects directly from borrowed elements, without cloning them into
+/// an owned composite first. The written byte-stream is identical to the one of the equivalent
+/// owned [`ByteSerialize`](ByteSerialize) object, so it may be read back using
+/// [`from_bytes`](ByteSerialize::from_bytes) of the owned type.
+///
+/// `ByteSerializeRef` is implemented by default for:
+/// - References to `ByteSerialize` objects. (ex: `&u32`, `&[f32; 3]`).
+/// - Tuples of `ByteSerializeRef` objects up to size 16.
+///   (ex: `(&f32, &f64, &u16)`, `(&[u16; 4], (&u8, &u8))`).
+pub trait ByteSerializeRef {
+	/// Size of the serialization of the object in bytes.
+	fn byte_count(&self) -> usize;
+
+	/// Serialize self to a byte-stream.
+	///
+	/// The stream is guaranteed to be at least [`self.byte_count()`](Self::byte_count) large.
+	/// Exactly [`self.byte_count()`](Self::byte_count) bytes should be written!
+	fn to_bytes(&self, bytes: &mut [u8]);
+}
+
+#[cfg(feature = "bitflags")]
+mod flags;
+mod bulk;
+mod fixed;
+mod framed;
+mod newtype;
+mod option_array;
+#[cfg(feature = "serde")]
+mod serde_parcel;
 mod standard;
+mod tail;
+mod unorm;
+#[cfg(test)]
+pub mod test;
+
+pub use bulk::{FixedByteSerialize, slice_byte_count, slice_from_bytes, slice_to_bytes};
+pub use fixed::{Fixed, Fixed64};
+pub use framed::Framed;
+pub use option_array::OptionArray;
+#[cfg(feature = "serde")]
+pub use serde_parcel::SerdeParcel;
+pub use standard::{string_from_bytes_unchecked, vec_from_bytes_into};
+pub use tail::Tail;
+pub use unorm::{Unorm8, Unorm16};
+#[cfg(feature = "derive")]
+pub use gnet_derive::ByteSerialize;
 
 impl Display for SerializationError {
 	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
@@ -0,0 +1,165 @@
+//! Bulk serialization of slices of fixed-size elements.
+//!
+//! Slices are serialized as a `u32` element count followed by the elements. For elements that are
+//! [`FixedByteSerialize`](FixedByteSerialize) the elements are copied with a single length-checked
+//! copy instead of serializing each element separately.
+
+use super::{ByteSerialize, SerializationError};
+
+use std::mem::{size_of, size_of_val};
+
+/// A marker trait for [`ByteSerialize`](ByteSerialize) types that may be serialized in bulk.
+///
+/// # Safety
+/// Implementors must uphold all of the following:
+/// - The type has no padding bytes.
+/// - Any bit-pattern of [`size_of::<Self>()`](size_of) bytes is a valid value of the type.
+/// - On little-endian targets the serialization produced by [`to_bytes`](ByteSerialize::to_bytes)
+///   is exactly the in-memory representation of the value, consisting of
+///   [`size_of::<Self>()`](size_of) bytes.
+///
+/// These hold for `#[repr(C)]` structs of `FixedByteSerialize` fields without padding, that are
+/// serialized field by field in declaration order.
+pub unsafe trait FixedByteSerialize: ByteSerialize + Copy {}
+
+macro_rules! impl_fixed_byte_serialize {
+	($($type:ty),*) => {
+		$(unsafe impl FixedByteSerialize for $type {})*
+	};
+}
+
+impl_fixed_byte_serialize!(u8, i8, u16, i16, u32, i32, f32, u64, i64, f64, u128, i128, usize, isize);
+
+unsafe impl<T: FixedByteSerialize + Default, const N: usize> FixedByteSerialize for [T; N] where
+	[T; N]: ByteSerialize,
+{}
+
+/// Get the number of bytes the bulk serialization of provided slice takes.
+#[inline]
+pub fn slice_byte_count<T: FixedByteSerialize>(slice: &[T]) -> usize {
+	size_of::<u32>() + size_of_val(slice)
+}
+
+/// Serialize provided slice of fixed-size elements to a byte-stream.
+///
+/// The stream must be at least [`slice_byte_count(slice)`](slice_byte_count) large.
+pub fn slice_to_bytes<T: FixedByteSerialize>(slice: &[T], bytes: &mut [u8]) {
+	assert!(bytes.len() >= slice_byte_count(slice));
+	assert!(slice.len() <= u32::MAX as usize);
+	(slice.len() as u32).to_bytes(bytes);
+	let bytes = &mut bytes[size_of::<u32>() .. slice_byte_count(slice)];
+	if cfg!(target_endian = "little") {
+		// SAFETY: FixedByteSerialize guarantees the in-memory representation has no padding and
+		// is identical to the serialization.
+		let source = unsafe { std::slice::from_raw_parts(slice.as_ptr() as *const u8, bytes.len()) };
+		bytes.copy_from_slice(source);
+	} else {
+		for (item, bytes) in slice.iter().zip(bytes.chunks_exact_mut(size_of::<T>())) {
+			item.to_bytes(bytes);
+		}
+	}
+}
+
+/// Deserialize a vector of fixed-size elements serialized with [`slice_to_bytes`](slice_to_bytes).
+///
+/// Returns the vector and the number of bytes read.
+pub fn slice_from_bytes<T: FixedByteSerialize>(bytes: &[u8]) -> Result<(Vec<T>, usize), SerializationError> {
+	let (count, offset) = u32::from_bytes(bytes)?;
+	let element_byte_count = (count as usize)
+		.checked_mul(size_of::<T>())
+		.ok_or(SerializationError::BufferOverflow)?;
+	let bytes = bytes
+		.get(offset .. offset + element_byte_count)
+		.ok_or(SerializationError::BufferOverflow)?;
+	let mut result = Vec::<T>::with_capacity(count as usize);
+	if cfg!(target_endian = "little") {
+		// SAFETY: the vector has the capacity for `count` elements, FixedByteSerialize guarantees
+		// any bit-pattern is a valid element.
+		unsafe {
+			std::ptr::copy_nonoverlapping(bytes.as_ptr(), result.as_mut_ptr() as *mut u8, bytes.len());
+			result.set_len(count as usize);
+		}
+	} else {
+		for bytes in bytes.chunks_exact(size_of::<T>()) {
+			result.push(T::from_bytes(bytes)?.0);
+		}
+	}
+	Ok((result, offset + element_byte_count))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[derive(Debug, Default, Clone, Copy, PartialEq)]
+	#[repr(C)]
+	struct Vertex {
+		position: [f32; 3],
+		weight: f32,
+	}
+
+	impl ByteSerialize for Vertex {
+		fn byte_count(&self) -> usize {
+			self.position.byte_count() + self.weight.byte_count()
+		}
+
+		fn to_bytes(&self, bytes: &mut [u8]) {
+			self.position.to_bytes(bytes);
+			self.weight.to_bytes(&mut bytes[self.position.byte_count() ..]);
+		}
+
+		fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), SerializationError> {
+			let (position, offset) = <[f32; 3]>::from_bytes(bytes)?;
+			let (weight, extra_offset) = f32::from_bytes(&bytes[offset ..])?;
+			Ok((Self { position, weight }, offset + extra_offset))
+		}
+	}
+
+	unsafe impl FixedByteSerialize for Vertex {}
+
+	fn vertices() -> Vec<Vertex> {
+		(0 .. 5)
+			.map(|i| Vertex { position: [i as f32, -1.5 * i as f32, 0.25], weight: 1.0 / (i + 1) as f32 })
+			.collect()
+	}
+
+	#[test]
+	fn bulk_matches_element_wise() {
+		let vertices = vertices();
+		let mut element_wise = vec![0; size_of::<u32>()];
+		(vertices.len() as u32).to_bytes(&mut element_wise);
+		for vertex in vertices.iter() {
+			let offset = element_wise.len();
+			element_wise.resize(offset + vertex.byte_count(), 0);
+			vertex.to_bytes(&mut element_wise[offset ..]);
+		}
+
+		let mut bulk = vec![0; slice_byte_count(&vertices)];
+		slice_to_bytes(&vertices, &mut bulk);
+
+		assert_eq!(bulk, element_wise);
+		assert_eq!(slice_from_bytes::<Vertex>(&bulk), Ok((vertices, bulk.len())));
+	}
+
+	#[test]
+	fn bulk_rejects_truncated_slice() {
+		let vertices = vertices();
+		let mut bytes = vec![0; slice_byte_count(&vertices)];
+		slice_to_bytes(&vertices, &mut bytes);
+
+		assert_eq!(
+			slice_from_bytes::<Vertex>(&bytes[.. bytes.len() - 1]),
+			Err(SerializationError::BufferOverflow),
+		);
+		assert_eq!(slice_from_bytes::<u64>(&[0xFF; 8]), Err(SerializationError::BufferOverflow));
+	}
+
+	#[test]
+	fn empty_slice_serializes_as_count() {
+		let mut bytes = [0xFF; 4];
+		slice_to_bytes::<u16>(&[], &mut bytes);
+
+		assert_eq!(bytes, [0; 4]);
+		assert_eq!(slice_from_bytes::<u16>(&bytes), Ok((Vec::new(), 4)));
+	}
+}
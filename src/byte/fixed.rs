@@ -0,0 +1,171 @@
+//! Fixed-point number types for deterministic arithmetic.
+//!
+//! Floating point operations may produce different results on different platforms, which breaks
+//! lockstep simulations. Fixed-point numbers are integers under the hood, so identical operations
+//! always produce identical results.
+
+use super::{ByteSerialize, SerializationError};
+
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+macro_rules! impl_fixed {
+	($(#[$meta:meta])* $name:ident, $bits:ty, $wide:ty) => {
+		$(#[$meta])*
+		#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+		pub struct $name<const FRAC_BITS: u32>($bits);
+
+		impl<const FRAC_BITS: u32> $name<FRAC_BITS> {
+			/// Value of `1` in the underlying representation.
+			const ONE: $bits = 1 << FRAC_BITS;
+
+			/// Construct a fixed-point number from its underlying representation.
+			#[inline]
+			pub const fn from_bits(bits: $bits) -> Self {
+				Self(bits)
+			}
+
+			/// Get the underlying representation of the fixed-point number.
+			#[inline]
+			pub const fn to_bits(self) -> $bits {
+				self.0
+			}
+		}
+
+		impl<const FRAC_BITS: u32> From<f32> for $name<FRAC_BITS> {
+			/// Convert provided float to the nearest representable fixed-point number.
+			///
+			/// Values outside of the representable range saturate, `NaN` converts to `0`.
+			#[inline]
+			fn from(value: f32) -> Self {
+				Self((value as f64 * Self::ONE as f64).round() as $bits)
+			}
+		}
+
+		impl<const FRAC_BITS: u32> From<$name<FRAC_BITS>> for f32 {
+			#[inline]
+			fn from(value: $name<FRAC_BITS>) -> Self {
+				(value.0 as f64 / $name::<FRAC_BITS>::ONE as f64) as f32
+			}
+		}
+
+		impl<const FRAC_BITS: u32> Add for $name<FRAC_BITS> {
+			type Output = Self;
+			#[inline]
+			fn add(self, rhs: Self) -> Self {
+				Self(self.0 + rhs.0)
+			}
+		}
+
+		impl<const FRAC_BITS: u32> Sub for $name<FRAC_BITS> {
+			type Output = Self;
+			#[inline]
+			fn sub(self, rhs: Self) -> Self {
+				Self(self.0 - rhs.0)
+			}
+		}
+
+		impl<const FRAC_BITS: u32> Mul for $name<FRAC_BITS> {
+			type Output = Self;
+			#[inline]
+			fn mul(self, rhs: Self) -> Self {
+				Self(((self.0 as $wide * rhs.0 as $wide) >> FRAC_BITS) as $bits)
+			}
+		}
+
+		impl<const FRAC_BITS: u32> Div for $name<FRAC_BITS> {
+			type Output = Self;
+			#[inline]
+			fn div(self, rhs: Self) -> Self {
+				Self((((self.0 as $wide) << FRAC_BITS) / rhs.0 as $wide) as $bits)
+			}
+		}
+
+		impl<const FRAC_BITS: u32> Neg for $name<FRAC_BITS> {
+			type Output = Self;
+			#[inline]
+			fn neg(self) -> Self {
+				Self(-self.0)
+			}
+		}
+
+		impl<const FRAC_BITS: u32> ByteSerialize for $name<FRAC_BITS> {
+			#[inline]
+			fn byte_count(&self) -> usize {
+				self.0.byte_count()
+			}
+
+			#[inline]
+			fn to_bytes(&self, bytes: &mut [u8]) {
+				self.0.to_bytes(bytes)
+			}
+
+			#[inline]
+			fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), SerializationError> {
+				let (bits, byte_count) = <$bits>::from_bytes(bytes)?;
+				Ok((Self(bits), byte_count))
+			}
+		}
+	};
+}
+
+impl_fixed!(
+	/// A 32-bit fixed-point number with `FRAC_BITS` fractional bits.
+	///
+	/// `FRAC_BITS` must be less than 31. Serialized as the underlying `i32`.
+	Fixed, i32, i64
+);
+
+impl_fixed!(
+	/// A 64-bit fixed-point number with `FRAC_BITS` fractional bits.
+	///
+	/// `FRAC_BITS` must be less than 63. Serialized as the underlying `i64`.
+	Fixed64, i64, i128
+);
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::byte::test::assert_round_trip;
+
+	#[test]
+	fn fixed_round_trips() {
+		assert_round_trip(&Fixed::<16>::from(1.5));
+		assert_round_trip(&Fixed::<8>::from(-1234.25));
+		assert_round_trip(&Fixed64::<32>::from(std::f32::consts::PI));
+		assert_round_trip(&Fixed64::<0>::from_bits(i64::MIN));
+	}
+
+	#[test]
+	fn fixed_converts_float() {
+		let value = Fixed::<16>::from(-2.25);
+
+		assert_eq!(value.to_bits(), -0x24000);
+		assert_eq!(f32::from(value), -2.25);
+
+		let value = Fixed::<8>::from(0.1);
+
+		assert!((f32::from(value) - 0.1).abs() <= 0.5 / 256.0);
+	}
+
+	#[test]
+	fn fixed_operations_are_deterministic() {
+		fn simulate() -> Vec<u8> {
+			let mut position = Fixed::<16>::from(1.5);
+			let mut velocity = Fixed::<16>::from(-2.25);
+			let drag = Fixed::<16>::from(0.9);
+			let step = Fixed::<16>::from(1.0) / Fixed::from(60.0);
+			for _ in 0 .. 100 {
+				position = position + velocity * step;
+				velocity = -(velocity * drag);
+			}
+			let mut bytes = vec![0; position.byte_count()];
+			position.to_bytes(&mut bytes);
+			bytes
+		}
+
+		// The result must not depend on the platform or the run.
+		for _ in 0 .. 10 {
+			assert_eq!(simulate(), [0xC2, 0x7A, 0x01, 0x00]);
+		}
+	}
+}
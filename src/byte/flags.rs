@@ -0,0 +1,91 @@
+//! [`ByteSerialize`](super::ByteSerialize) support for types generated by the
+//! [`bitflags`](https://docs.rs/bitflags) crate.
+
+/// Implement [`ByteSerialize`](crate::byte::ByteSerialize) for provided `bitflags` types.
+///
+/// The flags are serialized as their backing integer. Deserializing a value with any unknown
+/// bits set results in
+/// [`SerializationError::UnexpectedValue`](crate::byte::SerializationError::UnexpectedValue).
+///
+/// ## Example
+/// ```
+/// bitflags::bitflags! {
+///     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///     pub struct Input: u8 {
+///         const JUMP = 0b01;
+///         const FIRE = 0b10;
+///     }
+/// }
+///
+/// gnet::byte_serialize_bitflags!(Input);
+/// ```
+#[macro_export]
+macro_rules! byte_serialize_bitflags {
+	($($name:ty),+ $(,)?) => {
+		$(
+			impl $crate::byte::ByteSerialize for $name {
+				#[inline]
+				fn byte_count(&self) -> usize {
+					$crate::byte::ByteSerialize::byte_count(&self.bits())
+				}
+
+				#[inline]
+				fn to_bytes(&self, bytes: &mut [u8]) {
+					$crate::byte::ByteSerialize::to_bytes(&self.bits(), bytes)
+				}
+
+				#[inline]
+				fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), $crate::byte::SerializationError> {
+					let (bits, byte_count) = $crate::byte::ByteSerialize::from_bytes(bytes)?;
+					match <$name>::from_bits(bits) {
+						Some(flags) => Ok((flags, byte_count)),
+						None => Err($crate::byte::SerializationError::UnexpectedValue),
+					}
+				}
+			}
+		)+
+	};
+}
+
+#[cfg(test)]
+mod test {
+	use crate::byte::{ByteSerialize, SerializationError};
+	use crate::byte::test::assert_round_trip;
+
+	bitflags::bitflags! {
+		#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+		struct Signal: u16 {
+			const REQUEST = 0x0001;
+			const ACCEPT = 0x0002;
+			const CLOSE = 0x0100;
+		}
+	}
+
+	crate::byte_serialize_bitflags!(Signal);
+
+	#[test]
+	fn flags_round_trip() {
+		assert_round_trip(&Signal::empty());
+		assert_round_trip(&Signal::REQUEST);
+		assert_round_trip(&(Signal::ACCEPT | Signal::CLOSE));
+		assert_round_trip(&Signal::all());
+	}
+
+	#[test]
+	fn flags_serialize_as_bits() {
+		let flags = Signal::REQUEST | Signal::CLOSE;
+		let mut bytes = [0; 2];
+		flags.to_bytes(&mut bytes);
+
+		assert_eq!(flags.byte_count(), 2);
+		assert_eq!(u16::from_bytes(&bytes), Ok((0x0101, 2)));
+	}
+
+	#[test]
+	fn unknown_flags_are_rejected() {
+		let mut bytes = [0; 2];
+		0x0004u16.to_bytes(&mut bytes);
+
+		assert_eq!(Signal::from_bytes(&bytes), Err(SerializationError::UnexpectedValue));
+	}
+}
@@ -0,0 +1,109 @@
+//! Length-delimited container of opaque serialized data.
+
+use super::{ByteSerialize, SerializationError};
+
+use std::mem::size_of;
+
+/// Length-delimited serialized value of an arbitrary type.
+///
+/// Serialized as a `u16` byte count followed by the bytes of the value. Readers may skip framed
+/// values of types they do not understand, which allows extending protocols in a forward
+/// compatible way.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Framed(Vec<u8>);
+
+impl Framed {
+	/// Serialize provided value into a new frame.
+	///
+	/// Fails with [`BufferOverflow`](SerializationError::BufferOverflow) if the value serializes
+	/// to more than `u16::MAX` bytes.
+	pub fn wrap<T: ByteSerialize>(value: &T) -> Result<Self, SerializationError> {
+		let byte_count = value.byte_count();
+		if byte_count > u16::MAX as usize {
+			return Err(SerializationError::BufferOverflow)
+		}
+		let mut bytes = vec![0; byte_count];
+		value.to_bytes(&mut bytes);
+		Ok(Self(bytes))
+	}
+
+	/// Deserialize the framed value as provided type.
+	///
+	/// Fails with [`UnexpectedValue`](SerializationError::UnexpectedValue) if the value does not
+	/// consume exactly the framed bytes.
+	pub fn unwrap<T: ByteSerialize>(&self) -> Result<T, SerializationError> {
+		let (value, byte_count) = T::from_bytes(&self.0)?;
+		if byte_count == self.0.len() {
+			Ok(value)
+		} else {
+			Err(SerializationError::UnexpectedValue)
+		}
+	}
+
+	/// Get the opaque bytes of the framed value.
+	#[inline]
+	pub fn bytes(&self) -> &[u8] {
+		&self.0
+	}
+
+	/// Get the number of bytes a serialized frame at the start of provided byte-stream takes,
+	/// without copying the framed value.
+	pub fn skip(bytes: &[u8]) -> Result<usize, SerializationError> {
+		let (byte_count, offset) = u16::from_bytes(bytes)?;
+		let end = offset + byte_count as usize;
+		if bytes.len() < end {
+			Err(SerializationError::BufferOverflow)
+		} else {
+			Ok(end)
+		}
+	}
+}
+
+impl ByteSerialize for Framed {
+	#[inline]
+	fn byte_count(&self) -> usize {
+		size_of::<u16>() + self.0.len()
+	}
+
+	fn to_bytes(&self, bytes: &mut [u8]) {
+		(self.0.len() as u16).to_bytes(bytes);
+		bytes[size_of::<u16>() .. size_of::<u16>() + self.0.len()].copy_from_slice(&self.0);
+	}
+
+	fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), SerializationError> {
+		let end = Self::skip(bytes)?;
+		Ok((Self(bytes[size_of::<u16>() .. end].to_vec()), end))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::byte::test::assert_round_trip;
+
+	#[test]
+	fn framed_round_trips() {
+		let framed = Framed::wrap(&(1u8, -2.5f64)).unwrap();
+
+		assert_round_trip(&framed);
+		assert_round_trip(&Framed::default());
+		assert_eq!(framed.unwrap::<(u8, f64)>(), Ok((1, -2.5)));
+		assert_eq!(framed.unwrap::<u64>(), Err(SerializationError::UnexpectedValue));
+	}
+
+	#[test]
+	fn unknown_framed_value_is_skipped() {
+		// A newer writer includes an extension the reader does not understand.
+		let message = (3u8, Framed::wrap(&[0xABu16; 5]).unwrap(), 0xDEAD_BEEFu32);
+		let mut bytes = vec![0; message.byte_count()];
+		message.to_bytes(&mut bytes);
+
+		let (version, mut offset) = u8::from_bytes(&bytes).unwrap();
+		offset += Framed::skip(&bytes[offset ..]).unwrap();
+		let (checksum, _) = u32::from_bytes(&bytes[offset ..]).unwrap();
+
+		assert_eq!(version, 3);
+		assert_eq!(checksum, 0xDEAD_BEEF);
+		assert_eq!(Framed::skip(&bytes[1 .. 10]), Err(SerializationError::BufferOverflow));
+	}
+}
@@ -0,0 +1,67 @@
+//! [`ByteSerialize`](super::ByteSerialize) support for single-field newtypes.
+
+/// Implement [`ByteSerialize`](crate::byte::ByteSerialize) for provided newtypes by delegating
+/// to the wrapped type.
+///
+/// The newtypes are serialized identically to the values they wrap.
+///
+/// ## Example
+/// ```
+/// #[derive(Debug, PartialEq)]
+/// struct Health(u16);
+///
+/// gnet::byte_serialize_newtype!(Health(u16));
+/// ```
+#[macro_export]
+macro_rules! byte_serialize_newtype {
+	($($name:ident($inner:ty)),+ $(,)?) => {
+		$(
+			impl $crate::byte::ByteSerialize for $name {
+				#[inline]
+				fn byte_count(&self) -> usize {
+					$crate::byte::ByteSerialize::byte_count(&self.0)
+				}
+
+				#[inline]
+				fn to_bytes(&self, bytes: &mut [u8]) {
+					$crate::byte::ByteSerialize::to_bytes(&self.0, bytes)
+				}
+
+				#[inline]
+				fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), $crate::byte::SerializationError> {
+					let (inner, byte_count) = <$inner as $crate::byte::ByteSerialize>::from_bytes(bytes)?;
+					Ok(($name(inner), byte_count))
+				}
+			}
+		)+
+	};
+}
+
+#[cfg(test)]
+mod test {
+	use crate::byte::ByteSerialize;
+	use crate::byte::test::assert_round_trip;
+
+	#[derive(Debug, PartialEq)]
+	struct Health(u16);
+
+	#[derive(Debug, PartialEq)]
+	struct Position([f32; 2]);
+
+	crate::byte_serialize_newtype!(Health(u16), Position([f32; 2]));
+
+	#[test]
+	fn newtype_serializes_as_wrapped() {
+		let health = Health(0xBEEF);
+		let mut bytes = [0; 2];
+		let mut wrapped_bytes = [0; 2];
+		health.to_bytes(&mut bytes);
+		health.0.to_bytes(&mut wrapped_bytes);
+
+		assert_eq!(health.byte_count(), health.0.byte_count());
+		assert_eq!(bytes, wrapped_bytes);
+
+		assert_round_trip(&health);
+		assert_round_trip(&Position([1.0, -0.5]));
+	}
+}
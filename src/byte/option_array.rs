@@ -0,0 +1,114 @@
+//! Compact serialization of arrays of optional values.
+
+use super::{ByteSerialize, SerializationError};
+
+/// An array of optional values serialized with a presence bitmap.
+///
+/// Serialized as an `N`-bit bitmap (`ceil(N / 8)` bytes) of present values, followed by only
+/// the present values. Sparse arrays thus cost a single bit per missing value, instead of a
+/// whole tag byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionArray<T, const N: usize>(pub [Option<T>; N]);
+
+impl<T, const N: usize> OptionArray<T, N> {
+	/// Number of bytes taken by the presence bitmap.
+	const BITMAP_BYTE_COUNT: usize = N.div_ceil(8);
+}
+
+impl<T, const N: usize> Default for OptionArray<T, N> {
+	#[inline]
+	fn default() -> Self {
+		Self([(); N].map(|_| None))
+	}
+}
+
+impl<T, const N: usize> From<[Option<T>; N]> for OptionArray<T, N> {
+	#[inline]
+	fn from(values: [Option<T>; N]) -> Self {
+		Self(values)
+	}
+}
+
+impl<T: ByteSerialize, const N: usize> ByteSerialize for OptionArray<T, N> {
+	fn byte_count(&self) -> usize {
+		Self::BITMAP_BYTE_COUNT + self.0.iter().flatten().map(ByteSerialize::byte_count).sum::<usize>()
+	}
+
+	fn to_bytes(&self, bytes: &mut [u8]) {
+		assert!(bytes.len() >= self.byte_count());
+		let (bitmap, bytes) = bytes.split_at_mut(Self::BITMAP_BYTE_COUNT);
+		for byte in bitmap.iter_mut() {
+			*byte = 0;
+		}
+		let mut offset = 0;
+		for (i, value) in self.0.iter().enumerate() {
+			if let Some(value) = value {
+				bitmap[i / 8] |= 1 << (i % 8);
+				value.to_bytes(&mut bytes[offset ..]);
+				offset += value.byte_count();
+			}
+		}
+	}
+
+	fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), SerializationError> {
+		if bytes.len() < Self::BITMAP_BYTE_COUNT {
+			return Err(SerializationError::BufferOverflow)
+		}
+		let bitmap = &bytes[.. Self::BITMAP_BYTE_COUNT];
+		if !N.is_multiple_of(8) && bitmap[Self::BITMAP_BYTE_COUNT - 1] >> (N % 8) != 0 {
+			return Err(SerializationError::UnexpectedValue)
+		}
+
+		let mut result = Self::default();
+		let mut offset = Self::BITMAP_BYTE_COUNT;
+		for (i, value) in result.0.iter_mut().enumerate() {
+			if bitmap[i / 8] & (1 << (i % 8)) != 0 {
+				let (item, item_bytes) = T::from_bytes(&bytes[offset ..])?;
+				*value = Some(item);
+				offset += item_bytes;
+			}
+		}
+		Ok((result, offset))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::byte::test::assert_round_trip;
+
+	fn sparse() -> OptionArray<u32, 16> {
+		let mut array = OptionArray::default();
+		array.0[0] = Some(0xDEAD_BEEF);
+		array.0[9] = Some(7);
+		array.0[15] = Some(0);
+		array
+	}
+
+	#[test]
+	fn option_array_round_trips() {
+		assert_round_trip(&sparse());
+		assert_round_trip(&OptionArray::<u32, 16>::default());
+		assert_round_trip(&OptionArray::from([Some(1u8), None, Some(3)]));
+		assert_round_trip(&OptionArray::from([Some(1.5f64); 9]));
+	}
+
+	#[test]
+	fn option_array_is_compact() {
+		let array = sparse();
+		let mut bytes = vec![0; array.byte_count()];
+		array.to_bytes(&mut bytes);
+
+		assert_eq!(bytes.len(), 2 + 3 * 4);
+		assert_eq!(&bytes[.. 2], [0b0000_0001, 0b1000_0010]);
+	}
+
+	#[test]
+	fn option_array_rejects_bits_past_end() {
+		assert_eq!(
+			OptionArray::<u8, 3>::from_bytes(&[0b0000_1000, 0]),
+			Err(SerializationError::UnexpectedValue),
+		);
+		assert_eq!(OptionArray::<u8, 3>::from_bytes(&[0b0000_0100]), Err(SerializationError::BufferOverflow));
+	}
+}
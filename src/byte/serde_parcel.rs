@@ -0,0 +1,118 @@
+//! [`ByteSerialize`](super::ByteSerialize) bridge for types implementing
+//! [`serde`](https://docs.rs/serde) traits.
+
+use super::{ByteSerialize, SerializationError};
+
+use bincode::{DefaultOptions, ErrorKind, Options};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use std::io::ErrorKind as IoErrorKind;
+
+/// A wrapper serializing any `serde` value with a compact binary encoding.
+///
+/// Allows using existing `serde`-derived messages as parcels, without conflicting with
+/// [`ByteSerialize`](ByteSerialize) implementations of the wrapped types. The value is encoded
+/// with [`bincode`](https://docs.rs/bincode) using variable-length integers, which is not
+/// self-describing, so both ends must agree on the type.
+///
+/// # Panics
+/// [`byte_count()`](ByteSerialize::byte_count) and [`to_bytes()`](ByteSerialize::to_bytes)
+/// panic if the value fails to serialize, ex: a sequence of unknown length.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SerdeParcel<T>(pub T);
+
+impl<T> SerdeParcel<T> {
+	/// Unwrap the serialized value.
+	#[inline]
+	pub fn into_inner(self) -> T {
+		self.0
+	}
+}
+
+impl<T> From<T> for SerdeParcel<T> {
+	#[inline]
+	fn from(value: T) -> Self {
+		Self(value)
+	}
+}
+
+impl<T: Serialize + DeserializeOwned> ByteSerialize for SerdeParcel<T> {
+	#[inline]
+	fn byte_count(&self) -> usize {
+		options().serialized_size(&self.0).expect("Failed to serialize SerdeParcel!") as usize
+	}
+
+	#[inline]
+	fn to_bytes(&self, bytes: &mut [u8]) {
+		options().serialize_into(bytes, &self.0).expect("Failed to serialize SerdeParcel!")
+	}
+
+	fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), SerializationError> {
+		let mut reader = bytes;
+		match options().deserialize_from(&mut reader) {
+			Ok(value) => Ok((Self(value), bytes.len() - reader.len())),
+			Err(error) => match *error {
+				ErrorKind::Io(error) if error.kind() == IoErrorKind::UnexpectedEof => {
+					Err(SerializationError::BufferOverflow)
+				},
+				_ => Err(SerializationError::UnexpectedValue),
+			},
+		}
+	}
+}
+
+#[inline]
+fn options() -> impl Options {
+	DefaultOptions::new()
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::byte::test::assert_round_trip;
+
+	use serde::Deserialize;
+
+	#[derive(Debug, PartialEq, Serialize, Deserialize)]
+	enum Kind {
+		Player,
+		Projectile { damage: u16 },
+	}
+
+	#[derive(Debug, PartialEq, Serialize, Deserialize)]
+	struct Spawn {
+		id: u32,
+		position: [f32; 3],
+		kind: Kind,
+		name: String,
+	}
+
+	#[test]
+	fn serde_parcel_round_trips() {
+		let spawn = SerdeParcel(Spawn {
+			id: 7,
+			position: [1.0, 2.0, 3.0],
+			kind: Kind::Projectile { damage: 300 },
+			name: "GNET".to_string(),
+		});
+
+		assert_round_trip(&spawn);
+		assert_round_trip(&SerdeParcel(vec![Some(1u64), None, Some(u64::MAX)]));
+		// Small integers take a single byte.
+		assert_eq!(SerdeParcel(7u32).byte_count(), 1);
+	}
+
+	#[test]
+	fn serde_parcel_reports_errors() {
+		let parcel = SerdeParcel("GNET SERDE".to_string());
+		let mut bytes = vec![0; parcel.byte_count()];
+		parcel.to_bytes(&mut bytes);
+
+		assert_eq!(
+			SerdeParcel::<String>::from_bytes(&bytes[.. bytes.len() - 1]),
+			Err(SerializationError::BufferOverflow),
+		);
+		assert_eq!(SerdeParcel::<bool>::from_bytes(&[2]), Err(SerializationError::UnexpectedValue));
+	}
+}
@@ -1,7 +1,8 @@
 //! Implementations of [`ByteSerialize`](super::ByteSerialize) for standard library types.
 
-use super::{ByteSerialize, SerializationError};
+use super::{ByteSerialize, ByteSerializeRef, SerializationError};
 
+use std::cmp::min;
 use std::mem::size_of;
 
 macro_rules! impl_byte_serialize_numeric {
@@ -77,6 +78,133 @@ impl ByteSerialize for bool {
 	}
 }
 
+/// Options are serialized as a discriminant byte (`0` for `None`, `1` for `Some`) followed by the
+/// contained value if there is one.
+impl<T: ByteSerialize> ByteSerialize for Option<T> {
+	#[inline]
+	fn byte_count(&self) -> usize {
+		1 + self.as_ref().map_or(0, T::byte_count)
+	}
+
+	fn to_bytes(&self, bytes: &mut [u8]) {
+		match self {
+			None => bytes[0] = 0,
+			Some(value) => {
+				bytes[0] = 1;
+				value.to_bytes(&mut bytes[1 ..]);
+			},
+		}
+	}
+
+	fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), SerializationError> {
+		match bytes.first() {
+			None => Err(SerializationError::BufferOverflow),
+			Some(0) => Ok((None, 1)),
+			Some(1) => {
+				let (value, byte_count) = T::from_bytes(&bytes[1 ..])?;
+				Ok((Some(value), 1 + byte_count))
+			},
+			Some(_) => Err(SerializationError::UnexpectedValue),
+		}
+	}
+}
+
+/// Vectors are serialized as a `u32` element count followed by the elements.
+///
+/// For vectors of [`FixedByteSerialize`](super::FixedByteSerialize) elements consider using
+/// [`slice_to_bytes`](super::slice_to_bytes), which produces the same bytes faster.
+impl<T: ByteSerialize> ByteSerialize for Vec<T> {
+	fn byte_count(&self) -> usize {
+		size_of::<u32>() + self.iter().map(T::byte_count).sum::<usize>()
+	}
+
+	fn to_bytes(&self, bytes: &mut [u8]) {
+		assert!(self.len() <= u32::MAX as usize);
+		(self.len() as u32).to_bytes(bytes);
+		let mut offset = size_of::<u32>();
+		for item in self {
+			item.to_bytes(&mut bytes[offset ..]);
+			offset += item.byte_count();
+		}
+	}
+
+	fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), SerializationError> {
+		let mut result = Vec::new();
+		let byte_count = vec_from_bytes_into(bytes, &mut result)?;
+		Ok((result, byte_count))
+	}
+}
+
+/// Deserialize a [`Vec`](Vec) into provided vector, reusing its capacity.
+///
+/// Clears the vector and refills it with the deserialized elements, which avoids reallocating
+/// vectors that are received repeatedly, such as per-tick state. Returns the number of bytes read.
+/// On failure the contents of the vector are unspecified.
+///
+/// # Note
+/// Elements that are read from no bytes, such as `()`, are limited to the number of bytes that
+/// remain in the stream, so that a forged count does not stall the deserialization.
+pub fn vec_from_bytes_into<T: ByteSerialize>(bytes: &[u8], vec: &mut Vec<T>) -> Result<usize, SerializationError> {
+	let (count, mut offset) = u32::from_bytes(bytes)?;
+	vec.clear();
+	// Every element takes at least a byte, except for empty types, so a declared count
+	// exceeding the stream can not be satisfied and must not cause a huge allocation.
+	vec.reserve(min(count as usize, bytes.len() - offset));
+	for index in 0 .. count {
+		let (item, byte_count) = T::from_bytes(&bytes[offset ..])?;
+		if byte_count == 0 && (count - index) as usize > bytes.len() - offset {
+			return Err(SerializationError::BufferOverflow)
+		}
+		vec.push(item);
+		offset += byte_count;
+	}
+	Ok(offset)
+}
+
+/// Get the length-prefixed UTF-8 bytes at the start of provided byte-stream.
+///
+/// Returns the bytes and the total number of bytes read.
+fn read_string_bytes(bytes: &[u8]) -> Result<(&[u8], usize), SerializationError> {
+	let (len, offset) = u32::from_bytes(bytes)?;
+	let end = offset + len as usize;
+	let string = bytes.get(offset .. end).ok_or(SerializationError::BufferOverflow)?;
+	Ok((string, end))
+}
+
+/// Strings are serialized as a `u32` byte count followed by their UTF-8 bytes.
+impl ByteSerialize for String {
+	#[inline]
+	fn byte_count(&self) -> usize {
+		size_of::<u32>() + self.len()
+	}
+
+	fn to_bytes(&self, bytes: &mut [u8]) {
+		assert!(self.len() <= u32::MAX as usize);
+		(self.len() as u32).to_bytes(bytes);
+		bytes[size_of::<u32>() .. self.byte_count()].copy_from_slice(self.as_bytes());
+	}
+
+	fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), SerializationError> {
+		let (string, byte_count) = read_string_bytes(bytes)?;
+		Ok((String::from_utf8(string.to_vec())?, byte_count))
+	}
+}
+
+/// Deserialize a [`String`](String) without validating that it is UTF-8.
+///
+/// Identical to [`String::from_bytes`](ByteSerialize::from_bytes), but skips the validation pass,
+/// which is noticeable for large strings. The length prefix is still checked against the stream.
+///
+/// # Safety
+/// **The string bytes of the stream must be valid UTF-8!** Only use this on trusted channels that
+/// guarantee it, such as ones where both ends are the same program. Data received from the network
+/// is *not* trusted, any peer may send arbitrary bytes. Invalid UTF-8 in a `String` is undefined
+/// behavior, see [`String::from_utf8_unchecked`](String::from_utf8_unchecked).
+pub unsafe fn string_from_bytes_unchecked(bytes: &[u8]) -> Result<(String, usize), SerializationError> {
+	let (string, byte_count) = read_string_bytes(bytes)?;
+	Ok((String::from_utf8_unchecked(string.to_vec()), byte_count))
+}
+
 macro_rules! impl_byte_serialize_generic_array {
 	() => {};
 	($count:literal) => {
@@ -160,6 +288,32 @@ macro_rules! impl_byte_serialize_tuple {
 			}
 		}
 
+		impl<$($name: ByteSerializeRef),+> ByteSerializeRef for ($($name,)+) {
+			#[inline]
+			fn byte_count(&self) -> usize {
+				let mut result = 0;
+				$(
+					result += self.$index.byte_count();
+				)+
+				result
+			}
+
+			#[inline]
+			#[allow(unused_assignments)]
+			fn to_bytes(&self, bytes: &mut [u8]) {
+				let mut offset = 0;
+				// cache sizes of elements
+				$(let $element = self.$index.byte_count();)+
+				// calculate total size
+				$(offset += $element;)+
+				// write individual elements
+				$(
+					offset -= $element;
+					self.$index.to_bytes(&mut bytes[offset..]);
+				)+
+			}
+		}
+
 		peel_impl_byte_serialize_tuple!{$(($name, $element, $index),)+}
 	};
 }
@@ -168,11 +322,24 @@ macro_rules! peel_impl_byte_serialize_tuple {
 	($first:expr, $(($name:ident, $element:ident, $index:tt),)*) => { impl_byte_serialize_tuple!{$(($name, $element, $index),)*} }
 }
 
-impl_byte_serialize_tuple! { (T11, e11, 11), (T10, e10, 10), (T9, e9, 9), (T8, e8, 8), (T7, e7, 7), (T6, e6, 6), (T5, e5, 5), (T4, e4, 4), (T3, e3, 3), (T2, e2, 2), (T1, e1, 1), (T0, e0, 0), }
+impl_byte_serialize_tuple! { (T15, e15, 15), (T14, e14, 14), (T13, e13, 13), (T12, e12, 12), (T11, e11, 11), (T10, e10, 10), (T9, e9, 9), (T8, e8, 8), (T7, e7, 7), (T6, e6, 6), (T5, e5, 5), (T4, e4, 4), (T3, e3, 3), (T2, e2, 2), (T1, e1, 1), (T0, e0, 0), }
+
+impl<T: ByteSerialize> ByteSerializeRef for &T {
+	#[inline]
+	fn byte_count(&self) -> usize {
+		ByteSerialize::byte_count(*self)
+	}
+
+	#[inline]
+	fn to_bytes(&self, bytes: &mut [u8]) {
+		ByteSerialize::to_bytes(*self, bytes)
+	}
+}
 
 #[cfg(test)]
 mod test {
-	use super::ByteSerialize;
+	use super::{ByteSerialize, ByteSerializeRef, SerializationError, string_from_bytes_unchecked, vec_from_bytes_into};
+	use crate::byte::test::assert_round_trip;
 
 	#[test]
 	fn u32_serializes() {
@@ -242,6 +409,17 @@ mod test {
 		assert_eq!(original, deserialized);
 	}
 
+	#[test]
+	fn unit_serializes_to_nothing() {
+		let mut bytes = [];
+
+		assert_eq!(().byte_count(), 0);
+
+		().to_bytes(&mut bytes);
+
+		assert_eq!(<()>::from_bytes(&bytes), Ok(((), 0)));
+	}
+
 	#[test]
 	fn single_element_tuple_serializes() {
 		let original: (u32,) = (0xDEAD_BEEF,);
@@ -294,4 +472,246 @@ mod test {
 		assert_eq!(byte_count, EXPECTED_BYTE_COUNT);
 		assert_eq!(original, deserialized);
 	}
+
+	type SixteenElementTuple = (
+		u8, i8, u16, i16, u32, i32, u64, i64, f32, f64, bool, [u8; 3], (u16, u8), usize, isize, u128,
+	);
+
+	fn sixteen_element_tuple() -> SixteenElementTuple {
+		(1, -2, 3, -4, 5, -6, 7, -8, 9.5, -10.25, true, [12, 13, 14], (15, 16), 17, -18, 19)
+	}
+
+	#[test]
+	fn sixteen_element_tuple_serializes() {
+		let original = sixteen_element_tuple();
+		let byte_count = ByteSerialize::byte_count(&original);
+		let mut bytes = vec![0xFF; byte_count];
+
+		ByteSerialize::to_bytes(&original, &mut bytes);
+		let (deserialized, processed_byte_count) = SixteenElementTuple::from_bytes(&bytes).unwrap();
+
+		assert_eq!(processed_byte_count, byte_count);
+
+		// The standard library does not implement `PartialEq` for tuples this large.
+		let mut reserialized_bytes = vec![0x00; byte_count];
+		ByteSerialize::to_bytes(&deserialized, &mut reserialized_bytes);
+		assert_eq!(bytes, reserialized_bytes);
+		assert_eq!(original.0, deserialized.0);
+		assert_eq!(original.11, deserialized.11);
+		assert_eq!(original.15, deserialized.15);
+	}
+
+	#[test]
+	fn borrowed_tuple_serializes_as_owned() {
+		let original = sixteen_element_tuple();
+		let borrowed = (
+			&original.0, &original.1, &original.2, &original.3,
+			&original.4, &original.5, &original.6, &original.7,
+			&original.8, &original.9, &original.10, &original.11,
+			&original.12, &original.13, &original.14, &original.15,
+		);
+		let byte_count = ByteSerialize::byte_count(&original);
+
+		assert_eq!(ByteSerializeRef::byte_count(&borrowed), byte_count);
+
+		let mut owned_bytes = vec![0xFF; byte_count];
+		let mut borrowed_bytes = vec![0x00; byte_count];
+
+		ByteSerialize::to_bytes(&original, &mut owned_bytes);
+		ByteSerializeRef::to_bytes(&borrowed, &mut borrowed_bytes);
+
+		assert_eq!(owned_bytes, borrowed_bytes);
+	}
+
+	#[test]
+	fn numerics_round_trip() {
+		assert_round_trip(&u8::MAX);
+		assert_round_trip(&i8::MIN);
+		assert_round_trip(&0xBEEFu16);
+		assert_round_trip(&-0x0EEFi16);
+		assert_round_trip(&0xDEAD_BEEFu32);
+		assert_round_trip(&i32::MIN);
+		assert_round_trip(&u64::MAX);
+		assert_round_trip(&i64::MIN);
+		assert_round_trip(&u128::MAX);
+		assert_round_trip(&i128::MIN);
+		assert_round_trip(&usize::MAX);
+		assert_round_trip(&isize::MIN);
+		assert_round_trip(&std::f32::consts::E);
+		assert_round_trip(&-std::f64::consts::PI);
+		assert_round_trip(&true);
+		assert_round_trip(&());
+	}
+
+	#[test]
+	fn arrays_round_trip() {
+		assert_round_trip(&[0xDEAD_BEEFu32]);
+		assert_round_trip(&[1.0f32, 2.0, 3.0]);
+		assert_round_trip(&[[1u8, 2], [3, 4], [5, 6]]);
+		assert_round_trip(&[(1u16, false); 32]);
+	}
+
+	#[test]
+	fn tuples_round_trip() {
+		assert_round_trip(&(0xDEAD_BEEFu32,));
+		assert_round_trip(&(1u8, -2i64, 3.0f32));
+		assert_round_trip(&((1i32, 2isize), 3usize));
+		assert_round_trip(&([1u16, 2, 3, 4], 5u16, (true, [false; 3])));
+	}
+
+	#[test]
+	fn option_serializes() {
+		let mut bytes = [0; 5];
+
+		Some(0xDEAD_BEEFu32).to_bytes(&mut bytes);
+
+		assert_eq!(bytes, [1, 0xEF, 0xBE, 0xAD, 0xDE]);
+		assert_eq!(Option::<u32>::from_bytes(&bytes), Ok((Some(0xDEAD_BEEF), 5)));
+		assert_eq!(Option::<u32>::from_bytes(&bytes[.. 4]), Err(SerializationError::BufferOverflow));
+		assert_eq!(None::<u32>.byte_count(), 1);
+		assert_eq!(Option::<u32>::from_bytes(&[0]), Ok((None, 1)));
+		assert_eq!(Option::<u32>::from_bytes(&[2, 0, 0, 0, 0]), Err(SerializationError::UnexpectedValue));
+		assert_eq!(Option::<u32>::from_bytes(&[]), Err(SerializationError::BufferOverflow));
+	}
+
+	#[test]
+	fn options_round_trip() {
+		assert_round_trip(&Some(-1.5f64));
+		assert_round_trip(&None::<u16>);
+		assert_round_trip(&Some("GNET".to_string()));
+		assert_round_trip(&None::<String>);
+		assert_round_trip(&(Some(Some(3u8)), vec![None, Some(true)]));
+	}
+
+	#[test]
+	fn vec_serializes() {
+		let original = vec![(1u8, 2.5f32), (3, -4.0)];
+		let mut bytes = vec![0; original.byte_count()];
+
+		assert_eq!(bytes.len(), 4 + 2 * 5);
+
+		original.to_bytes(&mut bytes);
+
+		assert_eq!(&bytes[.. 4], &[2, 0, 0, 0]);
+		assert_eq!(Vec::<(u8, f32)>::from_bytes(&bytes), Ok((original, 14)));
+		assert_eq!(Vec::<(u8, f32)>::from_bytes(&bytes[.. 13]), Err(SerializationError::BufferOverflow));
+		assert_eq!(Vec::<u8>::from_bytes(&[0xFF, 0xFF, 0xFF, 0xFF, 1]), Err(SerializationError::BufferOverflow));
+	}
+
+	#[test]
+	fn vec_of_empty_elements_is_bounded() {
+		assert_eq!(Vec::<()>::from_bytes(&[0xFF, 0xFF, 0xFF, 0xFF]), Err(SerializationError::BufferOverflow));
+		assert_eq!(Vec::<()>::from_bytes(&[2, 0, 0, 0, 0, 0]), Ok((vec![(), ()], 4)));
+		assert_round_trip(&Vec::<()>::new());
+	}
+
+	#[test]
+	fn vec_is_deserialized_in_place() {
+		let mut vec = Vec::<u16>::with_capacity(8);
+		let capacity = vec.capacity();
+		let ptr = vec.as_ptr();
+
+		for len in [3, 8, 0, 5] {
+			let original: Vec<u16> = (0 .. len).collect();
+			let mut bytes = vec![0; original.byte_count()];
+			original.to_bytes(&mut bytes);
+
+			assert_eq!(vec_from_bytes_into(&bytes, &mut vec), Ok(bytes.len()));
+			assert_eq!(vec, original);
+			assert_eq!(vec.capacity(), capacity);
+			assert_eq!(vec.as_ptr(), ptr);
+		}
+	}
+
+	#[test]
+	fn vecs_round_trip() {
+		assert_round_trip(&Vec::<u32>::new());
+		assert_round_trip(&vec![vec![1u8, 2], vec![], vec![3]]);
+		assert_round_trip(&(7u16, vec![true, false], vec!["GNET".to_string()]));
+	}
+
+	#[test]
+	fn string_serializes() {
+		let original = "Grüß dich!".to_string();
+		let mut bytes = vec![0; original.byte_count()];
+
+		original.to_bytes(&mut bytes);
+
+		assert_eq!(&bytes[.. 4], &[12, 0, 0, 0]);
+		assert_eq!(String::from_bytes(&bytes), Ok((original.clone(), 16)));
+		// SAFETY: the bytes were produced from a valid string.
+		assert_eq!(unsafe { string_from_bytes_unchecked(&bytes) }, Ok((original, 16)));
+		assert_eq!(String::from_bytes(&bytes[.. 15]), Err(SerializationError::BufferOverflow));
+		assert_eq!(String::from_bytes(&[1, 0, 0, 0, 0xFF]), Err(SerializationError::UnexpectedValue));
+		assert_round_trip(&String::new());
+	}
+
+	#[cfg(feature = "proptest")]
+	mod property {
+		use crate::byte::ByteSerialize;
+		use crate::byte::test::assert_round_trip;
+
+		use proptest::prelude::*;
+
+		proptest! {
+			#[test]
+			fn integers_round_trip(a: u8, b: i16, c: u32, d: i64, e: u128, f: isize) {
+				assert_round_trip(&a);
+				assert_round_trip(&b);
+				assert_round_trip(&c);
+				assert_round_trip(&d);
+				assert_round_trip(&e);
+				assert_round_trip(&f);
+			}
+
+			#[test]
+			fn floats_round_trip(a in prop::num::f32::ANY, b in prop::num::f64::ANY) {
+				// NaN values are not equal to themselves, compare bit patterns instead.
+				assert_round_trip(&a.to_bits());
+				assert_round_trip(&b.to_bits());
+				if !a.is_nan() {
+					assert_round_trip(&a);
+				}
+				if !b.is_nan() {
+					assert_round_trip(&b);
+				}
+			}
+
+			#[test]
+			fn arrays_round_trip(a: [u16; 7], b: [[i32; 4]; 4], c: [bool; 32]) {
+				assert_round_trip(&a);
+				assert_round_trip(&b);
+				assert_round_trip(&c);
+			}
+
+			#[test]
+			fn tuples_round_trip(a: (u8,), b: (u64, bool, i8), c: ((u16, [u8; 3]), i128, (bool,))) {
+				assert_round_trip(&a);
+				assert_round_trip(&b);
+				assert_round_trip(&c);
+			}
+
+			#[test]
+			fn vecs_round_trip(
+				a in prop::collection::vec(any::<u16>(), 0 .. 64),
+				b in prop::collection::vec(prop::collection::vec(any::<u8>(), 0 .. 8), 0 .. 8),
+				c in prop::collection::vec((any::<bool>(), any::<i32>()), 0 .. 16),
+			) {
+				assert_round_trip(&a);
+				assert_round_trip(&b);
+				assert_round_trip(&c);
+				prop_assert_eq!(a.byte_count(), 4 + 2 * a.len());
+				prop_assert_eq!(b.byte_count(), 4 + b.iter().map(|inner| 4 + inner.len()).sum::<usize>());
+				prop_assert_eq!(c.byte_count(), 4 + 5 * c.len());
+			}
+
+			#[test]
+			fn strings_round_trip(a in ".*", b in prop::collection::vec(".*", 0 .. 8)) {
+				assert_round_trip(&a);
+				assert_round_trip(&b);
+				prop_assert_eq!(a.byte_count(), 4 + a.len());
+				prop_assert_eq!(b.byte_count(), 4 + b.iter().map(|string| 4 + string.len()).sum::<usize>());
+			}
+		}
+	}
 }
@@ -0,0 +1,76 @@
+//! Unprefixed container of the remaining bytes of a byte-stream.
+
+use super::{ByteSerialize, SerializationError};
+
+/// All remaining bytes of a byte-stream.
+///
+/// Serialized as just its bytes, without a length prefix. Deserialization consumes *all* of the
+/// provided byte-stream, so a `Tail` must be the last serialized field of a message, otherwise
+/// the fields following it are read as part of it. Use [`Framed`](super::Framed) for opaque values
+/// elsewhere in a message.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Tail(Vec<u8>);
+
+impl Tail {
+	/// Construct a new tail of provided bytes.
+	#[inline]
+	pub fn new(bytes: Vec<u8>) -> Self {
+		Self(bytes)
+	}
+
+	/// Get the bytes of the tail.
+	#[inline]
+	pub fn bytes(&self) -> &[u8] {
+		&self.0
+	}
+
+	/// Get the owned bytes of the tail.
+	#[inline]
+	pub fn into_bytes(self) -> Vec<u8> {
+		self.0
+	}
+}
+
+impl From<Vec<u8>> for Tail {
+	#[inline]
+	fn from(bytes: Vec<u8>) -> Self {
+		Self(bytes)
+	}
+}
+
+impl ByteSerialize for Tail {
+	#[inline]
+	fn byte_count(&self) -> usize {
+		self.0.len()
+	}
+
+	#[inline]
+	fn to_bytes(&self, bytes: &mut [u8]) {
+		bytes[.. self.0.len()].copy_from_slice(&self.0);
+	}
+
+	#[inline]
+	fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), SerializationError> {
+		Ok((Self(bytes.to_vec()), bytes.len()))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::byte::test::assert_round_trip;
+
+	#[test]
+	fn tail_round_trips_after_fixed_fields() {
+		let message = (7u16, [1.5f32, -2.0], Tail::new(b"REMAINING BYTES".to_vec()));
+
+		assert_round_trip(&message);
+		assert_round_trip(&(3u8, Tail::default()));
+
+		let mut bytes = vec![0; message.byte_count()];
+		message.to_bytes(&mut bytes);
+
+		assert_eq!(bytes.len(), 2 + 8 + 15);
+		assert_eq!(<(u16, [f32; 2], Tail)>::from_bytes(&bytes[.. 12]).unwrap().0.2.bytes(), b"RE");
+	}
+}
@@ -0,0 +1,35 @@
+//! Generic functions for testing [`ByteSerialize`](ByteSerialize) implementations.
+
+use super::ByteSerialize;
+
+use std::fmt::Debug;
+
+/// Test that provided value survives a serialization round-trip.
+///
+/// Checks that:
+/// - [`to_bytes`](ByteSerialize::to_bytes) writes no more than
+///   [`byte_count`](ByteSerialize::byte_count) bytes.
+/// - [`from_bytes`](ByteSerialize::from_bytes) reads exactly the written bytes.
+/// - The deserialized value is equal to the original one.
+pub fn assert_round_trip<T: ByteSerialize + PartialEq + Debug>(value: &T) {
+	const GUARD_BYTE_COUNT: usize = 8;
+
+	let byte_count = value.byte_count();
+
+	// Use different guard values to catch writes of either one past the end.
+	for &guard in [0x00, 0xFF].iter() {
+		let mut bytes = vec![guard; byte_count + GUARD_BYTE_COUNT];
+		value.to_bytes(&mut bytes);
+
+		assert!(
+			bytes[byte_count ..].iter().all(|&byte| byte == guard),
+			"{:?} wrote more than its byte_count of {} bytes!", value, byte_count,
+		);
+
+		let (deserialized, processed_byte_count) = T::from_bytes(&bytes[.. byte_count])
+			.expect("Failed to deserialize serialized bytes!");
+
+		assert_eq!(processed_byte_count, byte_count);
+		assert_eq!(&deserialized, value);
+	}
+}
@@ -0,0 +1,110 @@
+//! Compact normalized float types.
+//!
+//! Many values are fractions in the `[0, 1]` range (ex: health, volume), which do not need the
+//! full precision of a float. Normalized types quantize such values to 1 or 2 bytes.
+
+use super::{ByteSerialize, SerializationError};
+
+macro_rules! impl_unorm {
+	($(#[$meta:meta])* $name:ident, $bits:ty) => {
+		$(#[$meta])*
+		#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+		pub struct $name($bits);
+
+		impl $name {
+			/// Construct a normalized number from its underlying representation.
+			#[inline]
+			pub const fn from_bits(bits: $bits) -> Self {
+				Self(bits)
+			}
+
+			/// Get the underlying representation of the normalized number.
+			#[inline]
+			pub const fn to_bits(self) -> $bits {
+				self.0
+			}
+		}
+
+		impl From<f32> for $name {
+			/// Convert provided float to the nearest representable normalized number.
+			///
+			/// Values outside of the `[0, 1]` range are clamped, `NaN` converts to `0`.
+			#[inline]
+			fn from(value: f32) -> Self {
+				Self((value.clamp(0.0, 1.0) * <$bits>::MAX as f32).round() as $bits)
+			}
+		}
+
+		impl From<$name> for f32 {
+			#[inline]
+			fn from(value: $name) -> Self {
+				value.0 as f32 / <$bits>::MAX as f32
+			}
+		}
+
+		impl ByteSerialize for $name {
+			#[inline]
+			fn byte_count(&self) -> usize {
+				self.0.byte_count()
+			}
+
+			#[inline]
+			fn to_bytes(&self, bytes: &mut [u8]) {
+				self.0.to_bytes(bytes)
+			}
+
+			#[inline]
+			fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), SerializationError> {
+				let (bits, byte_count) = <$bits>::from_bytes(bytes)?;
+				Ok((Self(bits), byte_count))
+			}
+		}
+	};
+}
+
+impl_unorm!(
+	/// A float in the `[0, 1]` range quantized to 256 steps.
+	///
+	/// Serialized as the underlying `u8`.
+	Unorm8, u8
+);
+
+impl_unorm!(
+	/// A float in the `[0, 1]` range quantized to 65536 steps.
+	///
+	/// Serialized as the underlying `u16`.
+	Unorm16, u16
+);
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::byte::test::assert_round_trip;
+
+	#[test]
+	fn unorm_round_trips() {
+		assert_round_trip(&Unorm8::from(0.5));
+		assert_round_trip(&Unorm16::from(0.25));
+		assert_eq!(Unorm8::from(0.5).byte_count(), 1);
+		assert_eq!(Unorm16::from(0.5).byte_count(), 2);
+	}
+
+	#[test]
+	fn unorm_converts_float() {
+		for i in 0 ..= 100 {
+			let value = i as f32 / 100.0;
+
+			assert!((f32::from(Unorm8::from(value)) - value).abs() <= 0.5 / 255.0 + f32::EPSILON);
+			assert!((f32::from(Unorm16::from(value)) - value).abs() <= 0.5 / 65535.0 + f32::EPSILON);
+		}
+	}
+
+	#[test]
+	fn unorm_clamps_out_of_range() {
+		assert_eq!(Unorm8::from(-0.5).to_bits(), 0);
+		assert_eq!(Unorm8::from(1.5).to_bits(), u8::MAX);
+		assert_eq!(Unorm16::from(f32::INFINITY).to_bits(), u16::MAX);
+		assert_eq!(Unorm16::from(f32::NAN).to_bits(), 0);
+		assert_eq!(f32::from(Unorm16::from(2.0)), 1.0);
+	}
+}
@@ -1,13 +1,26 @@
 //! High level [`Connection`](connection::Connection) functionality.
 
+pub mod any;
 pub mod id;
 pub mod packet;
 pub mod error;
+pub mod fragment;
 pub mod context;
-// pub mod listen;
+pub mod handshake;
+pub mod listen;
+pub mod throughput;
+pub mod loss;
+pub mod pool;
+pub mod rtt;
 
 /// Possible message that is passed by connections.
 pub trait Parcel: super::byte::ByteSerialize {}
 
 #[cfg(test)]
 impl Parcel for () {}
+
+#[cfg(test)]
+impl Parcel for u32 {}
+
+#[cfg(test)]
+impl Parcel for Vec<u8> {}
@@ -0,0 +1,133 @@
+//! Dynamically typed [`Parcel`](super::Parcel) wrapper.
+
+use crate::byte::{ByteSerialize, SerializationError};
+
+use super::Parcel;
+
+use std::mem::size_of;
+
+/// A tag identifying the concrete type of an [`AnyParcel`](AnyParcel).
+pub type ParcelTag = u16;
+
+/// A parcel of any [`ByteSerialize`](ByteSerialize) type, identified by a user-provided tag.
+///
+/// Allows connections to carry heterogeneous parcels without a user-defined enum. The wrapped
+/// value is stored serialized and is only deserialized on demand with [`get()`](Self::get),
+/// letting the receiver pick the concrete type by matching on the [`tag()`](Self::tag).
+///
+/// Serialized as the tag, followed by the `u16` byte count and the bytes of the wrapped value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnyParcel {
+	tag: ParcelTag,
+	bytes: Vec<u8>,
+}
+
+impl AnyParcel {
+	/// Wrap provided value, associating it with provided tag.
+	///
+	/// Fails with [`BufferOverflow`](SerializationError::BufferOverflow) if the value serializes
+	/// to more than `u16::MAX` bytes.
+	pub fn new<T: ByteSerialize>(tag: ParcelTag, value: &T) -> Result<Self, SerializationError> {
+		let byte_count = value.byte_count();
+		if byte_count > u16::MAX as usize {
+			return Err(SerializationError::BufferOverflow)
+		}
+		let mut bytes = vec![0; byte_count];
+		value.to_bytes(&mut bytes);
+		Ok(Self { tag, bytes })
+	}
+
+	/// Get the tag identifying the type of the wrapped value.
+	#[inline]
+	pub fn tag(&self) -> ParcelTag {
+		self.tag
+	}
+
+	/// Get the serialized bytes of the wrapped value.
+	#[inline]
+	pub fn bytes(&self) -> &[u8] {
+		&self.bytes
+	}
+
+	/// Deserialize the wrapped value as provided type.
+	///
+	/// Fails with [`UnexpectedValue`](SerializationError::UnexpectedValue) if the value does not
+	/// consume exactly the wrapped bytes.
+	pub fn get<T: ByteSerialize>(&self) -> Result<T, SerializationError> {
+		let (value, byte_count) = T::from_bytes(&self.bytes)?;
+		if byte_count == self.bytes.len() {
+			Ok(value)
+		} else {
+			Err(SerializationError::UnexpectedValue)
+		}
+	}
+}
+
+impl ByteSerialize for AnyParcel {
+	#[inline]
+	fn byte_count(&self) -> usize {
+		size_of::<ParcelTag>() + size_of::<u16>() + self.bytes.len()
+	}
+
+	fn to_bytes(&self, bytes: &mut [u8]) {
+		self.tag.to_bytes(bytes);
+		let bytes = &mut bytes[size_of::<ParcelTag>() ..];
+		(self.bytes.len() as u16).to_bytes(bytes);
+		bytes[size_of::<u16>() .. size_of::<u16>() + self.bytes.len()].copy_from_slice(&self.bytes);
+	}
+
+	fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), SerializationError> {
+		let (tag, offset) = ParcelTag::from_bytes(bytes)?;
+		let (byte_count, extra_offset) = u16::from_bytes(&bytes[offset ..])?;
+		let offset = offset + extra_offset;
+		let end = offset + byte_count as usize;
+		if bytes.len() < end {
+			return Err(SerializationError::BufferOverflow)
+		}
+		Ok((Self { tag, bytes: bytes[offset .. end].to_vec() }, end))
+	}
+}
+
+impl Parcel for AnyParcel {}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::byte::test::assert_round_trip;
+
+	const POSITION_TAG: ParcelTag = 1;
+	const CHAT_TAG: ParcelTag = 2;
+
+	#[test]
+	fn any_parcel_round_trips_different_types() {
+		let position = AnyParcel::new(POSITION_TAG, &[1.5f32, -2.0, 0.25]).unwrap();
+		let chat = AnyParcel::new(CHAT_TAG, &(7u64, [b'h', b'i'])).unwrap();
+
+		assert_round_trip(&position);
+		assert_round_trip(&chat);
+
+		let mut bytes = vec![0; position.byte_count() + chat.byte_count()];
+		position.to_bytes(&mut bytes);
+		chat.to_bytes(&mut bytes[position.byte_count() ..]);
+
+		let mut offset = 0;
+		while offset < bytes.len() {
+			let (parcel, byte_count) = AnyParcel::from_bytes(&bytes[offset ..]).unwrap();
+			match parcel.tag() {
+				POSITION_TAG => assert_eq!(parcel.get::<[f32; 3]>(), Ok([1.5, -2.0, 0.25])),
+				CHAT_TAG => assert_eq!(parcel.get::<(u64, [u8; 2])>(), Ok((7, [b'h', b'i']))),
+				tag => panic!("Unexpected parcel tag {}!", tag),
+			}
+			offset += byte_count;
+		}
+	}
+
+	#[test]
+	fn any_parcel_rejects_mismatched_type() {
+		let parcel = AnyParcel::new(POSITION_TAG, &0xDEAD_BEEFu32).unwrap();
+
+		assert_eq!(parcel.get::<u64>(), Err(SerializationError::BufferOverflow));
+		assert_eq!(parcel.get::<u16>(), Err(SerializationError::UnexpectedValue));
+		assert_eq!(AnyParcel::from_bytes(&[1, 0, 4, 0, 0]), Err(SerializationError::BufferOverflow));
+	}
+}
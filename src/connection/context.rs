@@ -1,10 +1,42 @@
 //! Connection context.
 
+use crate::byte::{ByteSerialize, SerializationError, slice_byte_count, slice_from_bytes, slice_to_bytes};
+
 use super::Parcel;
 use super::id::ConnectionId;
 use super::error::{BuildPacketError, ConnectionError};
+use super::fragment::{self, FragmentHeader, Reassembler, DEFAULT_REASSEMBLY_TIMEOUT};
+use super::packet;
+use super::packet::{DataPrelude, PacketHeader, Signal};
+
+use rand::random;
+
+use std::cmp::min;
+use std::collections::{HashMap, VecDeque};
+use std::mem::size_of;
+use std::time::{Duration, Instant};
+
+/// Identifier of a logical slot of [deduplicated](Context::push_deduplicated_parcel) parcels.
+pub type ParcelSlot = u32;
+
+/// Application-defined code explaining why a connection was closed.
+pub type DisconnectCode = u16;
+
+/// [`DisconnectCode`](DisconnectCode) of a connection closed for no particular reason.
+pub const NORMAL_DISCONNECT: DisconnectCode = 0;
 
-use std::marker::PhantomData;
+/// Default duration without received packets after which a connection is considered lost.
+///
+/// See [`Context::set_timeout()`](Context::set_timeout).
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default maximum number of parcels decoded from a single packet.
+///
+/// See [`Context::set_max_parcel_count()`](Context::set_max_parcel_count).
+pub const DEFAULT_MAX_PARCEL_COUNT: usize = 256;
+
+/// Number of parcels a [compacted](Context::compact) context keeps capacity for in each queue.
+const BASELINE_PARCEL_CAPACITY: usize = 16;
 
 /// State of a connection.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -23,21 +55,44 @@ pub enum ConnectionStatus {
 	/// may be caused by a sudden shutdown of the other end or due to network conditions.
 	Lost,
 
-	/// Connection has been explicitly closed by the other end.
+	/// Connection has been explicitly closed by either end.
 	///
 	/// Connection may only be dropped to free system resources.
 	Closed,
 }
 
+/// Traffic of a connection, used to detect lost connections.
+#[derive(Debug, Default, Clone, Copy)]
+struct Activity {
+	last_received_at: Option<Instant>,
+	last_sent_at: Option<Instant>,
+	received: bool,
+	sent: bool,
+}
+
 /// Connection context.
 ///
 /// Used for processing incoming and build outgoing datagrams.
+#[derive(Debug)]
 pub struct Context<P: Parcel> {
 	connection_id: ConnectionId,
 	status: ConnectionStatus,
-	buffer: Vec<u8>,
-
-	_message_type: PhantomData<P>,
+	handshake_id: DataPrelude,
+	incoming_parcels: VecDeque<(P, DataPrelude)>,
+	last_prelude: Option<DataPrelude>,
+	accept_payload: Vec<u8>,
+	reject_reason: Vec<u8>,
+	disconnect_reason: Option<(DisconnectCode, Vec<u8>)>,
+	timeout: Duration,
+	keep_alive_interval: Option<Duration>,
+	activity: Activity,
+	outgoing_volatile_parcels: VecDeque<P>,
+	outgoing_fragments: VecDeque<Vec<u8>>,
+	next_fragment_group: u16,
+	reassembler: Reassembler,
+	reassembly_timeout: Duration,
+	parcel_slots: HashMap<ParcelSlot, Vec<u8>>,
+	max_parcel_count: usize,
 }
 
 impl<P: Parcel> Context<P> {
@@ -49,9 +104,22 @@ impl<P: Parcel> Context<P> {
 		Self {
 			connection_id: 0,
 			status: ConnectionStatus::Pending,
-			buffer: Vec::new(),
-
-			_message_type: Default::default(),
+			handshake_id: random::<u32>().to_ne_bytes(),
+			incoming_parcels: VecDeque::new(),
+			last_prelude: None,
+			accept_payload: Vec::new(),
+			reject_reason: Vec::new(),
+			disconnect_reason: None,
+			timeout: DEFAULT_TIMEOUT,
+			keep_alive_interval: None,
+			activity: Activity::default(),
+			outgoing_volatile_parcels: VecDeque::new(),
+			outgoing_fragments: VecDeque::new(),
+			next_fragment_group: 0,
+			reassembler: Reassembler::default(),
+			reassembly_timeout: DEFAULT_REASSEMBLY_TIMEOUT,
+			parcel_slots: HashMap::new(),
+			max_parcel_count: DEFAULT_MAX_PARCEL_COUNT,
 		}
 	}
 
@@ -60,18 +128,189 @@ impl<P: Parcel> Context<P> {
 		Self {
 			connection_id,
 			status: ConnectionStatus::Open,
-			buffer: Vec::new(),
-			
-			_message_type: Default::default(),
+			handshake_id: [0; 4],
+			incoming_parcels: VecDeque::new(),
+			last_prelude: None,
+			accept_payload: Vec::new(),
+			reject_reason: Vec::new(),
+			disconnect_reason: None,
+			timeout: DEFAULT_TIMEOUT,
+			keep_alive_interval: None,
+			activity: Activity::default(),
+			outgoing_volatile_parcels: VecDeque::new(),
+			outgoing_fragments: VecDeque::new(),
+			next_fragment_group: 0,
+			reassembler: Reassembler::default(),
+			reassembly_timeout: DEFAULT_REASSEMBLY_TIMEOUT,
+			parcel_slots: HashMap::new(),
+			max_parcel_count: DEFAULT_MAX_PARCEL_COUNT,
 		}
 	}
 
+	/// Serialize the state of the connection, so it may be [restored](Self::restore_state) later,
+	/// possibly by another process.
+	///
+	/// Captures the connection id, status, handshake id and accept payload. Queued incoming and
+	/// outgoing parcels, [deduplication](Self::push_deduplicated_parcel) slots and settings are not
+	/// captured, neither is the endpoint the connection communicates through.
+	pub fn serialize_state(&self) -> Vec<u8> {
+		let status = match self.status {
+			ConnectionStatus::Open => 0u8,
+			ConnectionStatus::Pending => 1,
+			ConnectionStatus::Lost => 2,
+			ConnectionStatus::Closed => 3,
+		};
+		let header = (self.connection_id, status, self.handshake_id);
+		let mut bytes = vec![0; header.byte_count() + slice_byte_count(&self.accept_payload)];
+		header.to_bytes(&mut bytes);
+		slice_to_bytes(&self.accept_payload, &mut bytes[header.byte_count() ..]);
+		bytes
+	}
+
+	/// Restore a connection context from state produced by
+	/// [`serialize_state()`](Self::serialize_state).
+	///
+	/// State that is not captured is reset to defaults.
+	pub fn restore_state(bytes: &[u8]) -> Result<Self, SerializationError> {
+		let ((connection_id, status, handshake_id), offset) = <(ConnectionId, u8, DataPrelude)>::from_bytes(bytes)?;
+		let status = match status {
+			0 => ConnectionStatus::Open,
+			1 => ConnectionStatus::Pending,
+			2 => ConnectionStatus::Lost,
+			3 => ConnectionStatus::Closed,
+			_ => return Err(SerializationError::UnexpectedValue),
+		};
+		let (accept_payload, _) = slice_from_bytes(&bytes[offset ..])?;
+		Ok(Self {
+			connection_id,
+			status,
+			handshake_id,
+			accept_payload,
+			.. Self::pending()
+		})
+	}
+
 	/// Get the current status (state) of the connection.
 	#[inline]
 	pub fn status(&self) -> ConnectionStatus {
 		self.status
 	}
 
+	/// Check whether the connection is [`Open`](ConnectionStatus::Open).
+	#[inline]
+	pub fn is_open(&self) -> bool {
+		self.status == ConnectionStatus::Open
+	}
+
+	/// Get the duration without received packets after which the connection is considered lost.
+	#[inline]
+	pub fn timeout(&self) -> Duration {
+		self.timeout
+	}
+
+	/// Set the duration without received packets after which the connection is considered lost.
+	///
+	/// Defaults to [`DEFAULT_TIMEOUT`](DEFAULT_TIMEOUT). Returns
+	/// [`InvalidSetting`](ConnectionError::InvalidSetting) without changing the timeout if it is
+	/// zero or not longer than the [set](Self::set_keep_alive_interval) keep-alive interval.
+	#[inline]
+	pub fn set_timeout(&mut self, timeout: Duration) -> Result<(), ConnectionError> {
+		if timeout.is_zero() || self.keep_alive_interval.is_some_and(|interval| interval >= timeout) {
+			return Err(ConnectionError::InvalidSetting)
+		}
+		self.timeout = timeout;
+		Ok(())
+	}
+
+	/// Get the duration without sent packets after which a keep-alive packet should be sent.
+	///
+	/// Is half of the [`timeout()`](Self::timeout) unless [set](Self::set_keep_alive_interval).
+	#[inline]
+	pub fn keep_alive_interval(&self) -> Duration {
+		self.keep_alive_interval.unwrap_or(self.timeout / 2)
+	}
+
+	/// Set the duration without sent packets after which a keep-alive packet should be sent.
+	///
+	/// Should be shorter than the timeout of the other end, so that it does not consider the
+	/// connection lost while there is nothing to send.
+	///
+	/// Takes effect from the next [`needs_keep_alive()`](Self::needs_keep_alive) check, measured
+	/// from the last sent packet. Returns [`InvalidSetting`](ConnectionError::InvalidSetting)
+	/// without changing the interval if it is zero or not shorter than the
+	/// [`timeout()`](Self::timeout).
+	#[inline]
+	pub fn set_keep_alive_interval(&mut self, interval: Duration) -> Result<(), ConnectionError> {
+		if interval.is_zero() || interval >= self.timeout {
+			return Err(ConnectionError::InvalidSetting)
+		}
+		self.keep_alive_interval = Some(interval);
+		Ok(())
+	}
+
+	/// Get the duration an incomplete [fragmented](super::fragment) parcel is held for.
+	#[inline]
+	pub fn reassembly_timeout(&self) -> Duration {
+		self.reassembly_timeout
+	}
+
+	/// Set the duration an incomplete [fragmented](super::fragment) parcel is held for, before
+	/// being dropped.
+	///
+	/// Defaults to [`DEFAULT_REASSEMBLY_TIMEOUT`](DEFAULT_REASSEMBLY_TIMEOUT).
+	#[inline]
+	pub fn set_reassembly_timeout(&mut self, timeout: Duration) {
+		self.reassembly_timeout = timeout;
+	}
+
+	/// Advance the connection to provided time.
+	///
+	/// Packets [ingested](Self::ingest) and built since the previous update are considered to be
+	/// received and sent at provided time. An [`Open`](ConnectionStatus::Open) connection that did
+	/// not receive any packets for its [`timeout()`](Self::timeout) moves to
+	/// [`Lost`](ConnectionStatus::Lost) state. The first update only starts measuring the time.
+	///
+	/// Fragmented parcels that are incomplete for the [`reassembly_timeout()`](Self::reassembly_timeout)
+	/// are dropped. Each dropped parcel is reported by a single update returning
+	/// [`ReassemblyTimeout`](super::error::ReliabilityError::ReassemblyTimeout), parcels dropped at once are
+	/// reported by consecutive updates. The connection is advanced regardless.
+	///
+	/// Should be invoked regularly, the time resolution of the timeout is the update interval.
+	pub fn update(&mut self, now: Instant) -> Result<(), ConnectionError> {
+		let activity = &mut self.activity;
+		if activity.received || activity.last_received_at.is_none() {
+			activity.last_received_at = Some(now);
+		}
+		if activity.sent || activity.last_sent_at.is_none() {
+			activity.last_sent_at = Some(now);
+		}
+		activity.received = false;
+		activity.sent = false;
+		let result = self.reassembler.update(now, self.reassembly_timeout);
+
+		let last_received_at = activity.last_received_at.unwrap();
+		if self.status == ConnectionStatus::Open && now.saturating_duration_since(last_received_at) >= self.timeout {
+			self.status = ConnectionStatus::Lost;
+		}
+		result.map_err(ConnectionError::from)
+	}
+
+	/// Check whether a keep-alive packet should be sent at provided time, because no packets were
+	/// sent for the [`keep_alive_interval()`](Self::keep_alive_interval).
+	///
+	/// [`build_packet()`](Self::build_packet) builds a keep-alive packet when there are no parcels
+	/// to send. Relies on the time of the last [`update()`](Self::update).
+	pub fn needs_keep_alive(&self, now: Instant) -> bool {
+		match self.activity.last_sent_at {
+			Some(last_sent_at) => {
+				self.status == ConnectionStatus::Open
+					&& !self.activity.sent
+					&& now.saturating_duration_since(last_sent_at) >= self.keep_alive_interval()
+			},
+			None => false,
+		}
+	}
+
 	/// Get the connection id if the connection has one.
 	///
 	/// A [pending](ConnectionStatus::Pending) connection may not have a valid id yet.
@@ -83,11 +322,244 @@ impl<P: Parcel> Context<P> {
 		}
 	}
 
+	/// Get the payload the other end included in the response accepting the connection.
+	///
+	/// Is empty for connections that were not established by
+	/// [`ingest_response()`](Self::ingest_response).
+	#[inline]
+	pub fn accept_payload(&self) -> &[u8] {
+		&self.accept_payload
+	}
+
+	/// Get the reason the other end included in the response rejecting the connection.
+	///
+	/// Is empty for connections that were not rejected, or rejected without a reason.
+	#[inline]
+	pub fn reject_reason(&self) -> &[u8] {
+		&self.reject_reason
+	}
+
+	/// Get the code and message explaining why the connection was closed.
+	///
+	/// Is `None` unless the connection was closed by [`ingest()`](Self::ingest)ing or
+	/// [building](Self::build_close_packet) a closing packet.
+	#[inline]
+	pub fn disconnect_reason(&self) -> Option<(DisconnectCode, &[u8])> {
+		self.disconnect_reason.as_ref().map(|(code, message)| (*code, message.as_slice()))
+	}
+
+	/// Get the maximum number of parcels decoded from a single packet.
+	#[inline]
+	pub fn max_parcel_count(&self) -> usize {
+		self.max_parcel_count
+	}
+
+	/// Set the maximum number of parcels decoded from a single packet.
+	///
+	/// Packets containing more parcels are dropped as malformed, which bounds the work a single
+	/// crafted packet of many tiny parcels may cause. Defaults to
+	/// [`DEFAULT_MAX_PARCEL_COUNT`](DEFAULT_MAX_PARCEL_COUNT).
+	#[inline]
+	pub fn set_max_parcel_count(&mut self, count: usize) {
+		self.max_parcel_count = count;
+	}
+
+	/// Release memory held by internal buffers beyond a baseline capacity.
+	///
+	/// Incoming and outgoing queues grow to fit the largest burst of traffic and never shrink on
+	/// their own, so mostly idle connections should be compacted periodically. Queued data and
+	/// [deduplication](Self::push_deduplicated_parcel) slots are preserved.
+	pub fn compact(&mut self) {
+		self.incoming_parcels.shrink_to(BASELINE_PARCEL_CAPACITY);
+		self.outgoing_volatile_parcels.shrink_to(BASELINE_PARCEL_CAPACITY);
+		self.outgoing_fragments.shrink_to(BASELINE_PARCEL_CAPACITY);
+		self.parcel_slots.shrink_to(BASELINE_PARCEL_CAPACITY);
+	}
+
+	/// Process a response to a connection request built with
+	/// [`build_request_packet()`](Self::build_request_packet).
+	///
+	/// An accepting response opens the connection and assigns it the id provided by the other end,
+	/// which may then be retrieved with [`connection_id()`](Self::connection_id), along with the
+	/// [`accept_payload()`](Self::accept_payload). A rejecting response closes the connection, the
+	/// reason for the rejection may be retrieved with [`reject_reason()`](Self::reject_reason).
+	///
+	/// The connection must be in [`Pending`](ConnectionStatus::Pending) state! The only exception
+	/// is a retransmitted accepting response for an already [`Open`](ConnectionStatus::Open)
+	/// connection, which is ignored.
+	pub fn ingest_response(&mut self, packet: &[u8]) -> Result<(), ConnectionError> {
+		if self.status != ConnectionStatus::Pending && self.status != ConnectionStatus::Open {
+			return Err(ConnectionError::InvalidState)
+		}
+		if !packet::is_valid_connectionless(packet) {
+			return Err(ConnectionError::MalformedPacket)
+		}
+
+		let header = packet::read_header(packet);
+		if header.prelude != self.handshake_id {
+			return Err(ConnectionError::MalformedPacket)
+		}
+		if header.signal.is_signal_set(Signal::ConnectionAccepted) {
+			let payload = packet::get_parcel_segment(packet);
+			if payload.len() < size_of::<ConnectionId>() {
+				return Err(ConnectionError::MalformedPacket)
+			}
+			let connection_id = ConnectionId::from_le_bytes([payload[0], payload[1]]);
+			if connection_id == 0 {
+				return Err(ConnectionError::MalformedPacket)
+			}
+			match self.status {
+				ConnectionStatus::Pending => {
+					self.accept_payload = payload[size_of::<ConnectionId>() ..].to_vec();
+					self.connection_id = connection_id;
+					self.status = ConnectionStatus::Open;
+					Ok(())
+				},
+				_ if connection_id == self.connection_id => Ok(()),
+				_ => Err(ConnectionError::InvalidState),
+			}
+		} else if self.status != ConnectionStatus::Pending {
+			Err(ConnectionError::InvalidState)
+		} else if header.signal.is_signal_set(Signal::ConnectionClosed) {
+			self.reject_reason = packet::get_parcel_segment(packet).to_vec();
+			self.status = ConnectionStatus::Closed;
+			Ok(())
+		} else {
+			Err(ConnectionError::MalformedPacket)
+		}
+	}
+
+	/// Process a received packet associated with this connection.
+	///
+	/// All parcels of the packet are deserialized immediately and buffered to be popped with
+	/// [`pop_parcel()`](Self::pop_parcel). If any of the parcels fails to deserialize, or the
+	/// packet contains more than [`max_parcel_count()`](Self::max_parcel_count) parcels, the whole
+	/// packet is dropped.
+	///
+	/// A packet signalling the connection being closed by the other end moves the connection to
+	/// [`Closed`](ConnectionStatus::Closed) state, its payload is the
+	/// [`disconnect_reason()`](Self::disconnect_reason) instead of parcels. Parcels received before
+	/// that may still be popped.
+	///
+	/// Packets of other connections are rejected as
+	/// [`MalformedPacket`](ConnectionError::MalformedPacket) without affecting the connection,
+	/// which guards against misrouted or injected packets.
+	///
+	/// # Notes
+	/// Parcels that serialize to no bytes can not be transmitted, as the end of such parcels is
+	/// indistinguishable from the end of the parcel segment.
+	pub fn ingest(&mut self, packet: &[u8]) -> Result<(), ConnectionError> {
+		if self.status != ConnectionStatus::Open {
+			return Err(ConnectionError::InvalidState)
+		}
+		if !packet::is_valid_connected(packet) {
+			return Err(ConnectionError::MalformedPacket)
+		}
+
+		let header = packet::read_header(packet);
+		if header.connection_id != self.connection_id {
+			return Err(ConnectionError::MalformedPacket)
+		}
+		let parcel_segment = packet::get_parcel_segment(packet);
+		if header.signal.is_signal_set(Signal::ConnectionClosed) {
+			let reason = match parcel_segment.len() {
+				0 => (NORMAL_DISCONNECT, Vec::new()),
+				1 => return Err(ConnectionError::MalformedPacket),
+				_ => (DisconnectCode::from_le_bytes([parcel_segment[0], parcel_segment[1]]), parcel_segment[2 ..].to_vec()),
+			};
+			self.disconnect_reason = Some(reason);
+			self.close();
+			self.activity.received = true;
+			self.last_prelude = Some(header.prelude);
+			return Ok(())
+		}
+		if header.signal.is_signal_set(Signal::Fragment) {
+			let fragment_header = FragmentHeader::read(parcel_segment).ok_or(ConnectionError::MalformedPacket)?;
+			let fragment_data = &parcel_segment[FragmentHeader::BYTE_COUNT ..];
+			if let Some(bytes) = self.reassembler.insert(fragment_header, fragment_data)? {
+				match P::from_bytes(&bytes) {
+					Ok((parcel, byte_count)) if byte_count == bytes.len() => {
+						self.incoming_parcels.push_back((parcel, header.prelude));
+					},
+					result => return Err(result.err().unwrap_or(SerializationError::UnexpectedValue).into()),
+				}
+			}
+			self.activity.received = true;
+			self.last_prelude = Some(header.prelude);
+			return Ok(())
+		}
+		let previous_parcel_count = self.incoming_parcels.len();
+		let mut offset = 0;
+		while offset < parcel_segment.len() {
+			if self.incoming_parcels.len() - previous_parcel_count == self.max_parcel_count {
+				self.incoming_parcels.truncate(previous_parcel_count);
+				return Err(ConnectionError::MalformedPacket)
+			}
+			match P::from_bytes(&parcel_segment[offset ..]) {
+				Ok((parcel, byte_count)) if byte_count > 0 => {
+					self.incoming_parcels.push_back((parcel, header.prelude));
+					offset += byte_count;
+				},
+				result => {
+					self.incoming_parcels.truncate(previous_parcel_count);
+					return Err(result.err().unwrap_or(SerializationError::UnexpectedValue).into())
+				},
+			}
+		}
+		self.activity.received = true;
+		self.last_prelude = Some(header.prelude);
+
+		Ok(())
+	}
+
+	/// Get the data prelude of the most recently processed packet.
+	///
+	/// Unlike preludes returned with parcels, is updated by packets without any parcels as well.
+	#[inline]
+	pub fn last_prelude(&self) -> Option<DataPrelude> {
+		self.last_prelude
+	}
+
 	/// Get the next processed parcel.
 	///
 	/// Includes the data prelude from the network packet that the parcel was transmitted with.
+	///
+	/// Returns [`ConnectionError::Closed`](ConnectionError::Closed) once all parcels of a closed
+	/// connection have been popped.
 	pub fn pop_parcel(&mut self) -> Result<(P, [u8; 4]), ConnectionError> {
-		todo!()
+		self.incoming_parcels.pop_front().ok_or_else(|| self.no_parcels_error())
+	}
+
+	/// Get the next processed parcel if there is one.
+	///
+	/// Unlike [`pop_parcel()`](Self::pop_parcel) returns `Ok(None)` if there are no pending
+	/// parcels, reserving errors for actual failures, such as the connection being
+	/// [closed](ConnectionError::Closed).
+	pub fn try_recv(&mut self) -> Result<Option<(P, [u8; 4])>, ConnectionError> {
+		match self.pop_parcel() {
+			Ok(parcel) => Ok(Some(parcel)),
+			Err(ConnectionError::NoPendingParcels) => Ok(None),
+			Err(error) => Err(error),
+		}
+	}
+
+	/// Get the next processed parcel without consuming it.
+	///
+	/// The following invocation of [`pop_parcel()`](Self::pop_parcel) returns the same parcel.
+	pub fn peek_parcel(&self) -> Result<(&P, [u8; 4]), ConnectionError> {
+		self.incoming_parcels
+			.front()
+			.map(|(parcel, prelude)| (parcel, *prelude))
+			.ok_or_else(|| self.no_parcels_error())
+	}
+
+	/// Get the error describing why there are no parcels to pop.
+	fn no_parcels_error(&self) -> ConnectionError {
+		if self.status == ConnectionStatus::Closed {
+			ConnectionError::Closed
+		} else {
+			ConnectionError::NoPendingParcels
+		}
 	}
 
 	/// Queue provided parcel to be included in built packets.
@@ -95,8 +567,12 @@ impl<P: Parcel> Context<P> {
 	/// Reliable parcels are guaranteed to be delivered as long as the connection
 	/// is in a valid state. The order of delivery is not guaranteed however, for
 	/// order-dependent functionality use streams.
-	pub fn push_reliable_parcel(&mut self, parcel: P) -> Result<(), ConnectionError> {
-		todo!()
+	///
+	/// **NOTE**: reliable delivery is not implemented yet, so this always fails with
+	/// [`InvalidState`](ConnectionError::InvalidState). Use
+	/// [`push_volatile_parcel()`](Self::push_volatile_parcel) instead.
+	pub fn push_reliable_parcel(&mut self, _parcel: P) -> Result<(), ConnectionError> {
+		Err(ConnectionError::InvalidState)
 	}
 
 	/// Queue provided parcel to be included in built packets.
@@ -104,8 +580,40 @@ impl<P: Parcel> Context<P> {
 	/// Unreliable (volatile) parcels are delivered in a best-effort manner, however no
 	/// re-transmission occurs of the parcel was not received by the other end. The order
 	/// of delivery is not guaranteed, for order-dependent functionality use streams.
+	///
+	/// Parcels that serialize to no bytes can not be transmitted and are refused with
+	/// [`SerializationError::UnexpectedValue`](SerializationError::UnexpectedValue).
 	pub fn push_volatile_parcel(&mut self, parcel: P) -> Result<(), ConnectionError> {
-		todo!()
+		if self.status != ConnectionStatus::Open {
+			return Err(ConnectionError::InvalidState)
+		}
+		if parcel.byte_count() == 0 {
+			return Err(SerializationError::UnexpectedValue.into())
+		}
+		self.outgoing_volatile_parcels.push_back(parcel);
+		Ok(())
+	}
+
+	/// Queue provided volatile parcel unless it is identical to the last one queued for the
+	/// same logical slot.
+	///
+	/// Useful for idempotent state (ex: a "paused" flag), that would otherwise waste bandwidth
+	/// by repeatedly sending the same value. Parcels are compared by their serialization.
+	///
+	/// Returns whether the parcel was queued. See
+	/// [`push_volatile_parcel()`](Self::push_volatile_parcel) for delivery details.
+	pub fn push_deduplicated_parcel(&mut self, slot: ParcelSlot, parcel: P) -> Result<bool, ConnectionError> {
+		if self.status != ConnectionStatus::Open {
+			return Err(ConnectionError::InvalidState)
+		}
+		let mut bytes = vec![0; parcel.byte_count()];
+		parcel.to_bytes(&mut bytes);
+		if self.parcel_slots.get(&slot) == Some(&bytes) {
+			return Ok(false)
+		}
+		self.push_volatile_parcel(parcel)?;
+		self.parcel_slots.insert(slot, bytes);
+		Ok(true)
 	}
 
 	/// Attempt to read data from the connection stream into the provided buffer.
@@ -122,8 +630,11 @@ impl<P: Parcel> Context<P> {
 	/// # Note
 	/// Has consuming behavior, meaning repeated invocations will read exhaust internal stream
 	/// buffer.
-	pub fn read_from_stream(&mut self, buffer: &mut [u8]) -> Result<usize, ConnectionError> {
-		todo!()
+	///
+	/// **NOTE**: streams depend on reliable delivery, which is not implemented yet, so this always
+	/// fails with [`InvalidState`](ConnectionError::InvalidState).
+	pub fn read_from_stream(&mut self, _buffer: &mut [u8]) -> Result<usize, ConnectionError> {
+		Err(ConnectionError::InvalidState)
 	}
 
 	/// Write a given slice of bytes to the connection stream.
@@ -133,21 +644,809 @@ impl<P: Parcel> Context<P> {
 	/// [TCP](https://en.wikipedia.org/wiki/Transmission_Control_Protocol)-like functionality
 	/// for contiguous streams of data. Streams are transmitted with the same network packets
 	/// as reliable parcels, reducing overall data duplication for lost packets.
-	pub fn write_bytes_to_stream(&mut self, bytes: &[u8]) -> Result<(), ConnectionError> {
-		todo!()
+	///
+	/// **NOTE**: streams depend on reliable delivery, which is not implemented yet, so this always
+	/// fails with [`InvalidState`](ConnectionError::InvalidState).
+	pub fn write_bytes_to_stream(&mut self, _bytes: &[u8]) -> Result<(), ConnectionError> {
+		Err(ConnectionError::InvalidState)
+	}
+
+	/// Check whether there are queued parcels or fragments that were not built into packets yet.
+	///
+	/// Packets should be [built](Self::build_packet) until it returns `false` to send all of them.
+	#[inline]
+	pub fn has_queued_data(&self) -> bool {
+		!self.outgoing_volatile_parcels.is_empty() || !self.outgoing_fragments.is_empty()
+	}
+
+	/// Build the next packet that should be sent at provided time, if there is one.
+	///
+	/// A packet is built if there is [queued data](Self::has_queued_data) or a keep-alive packet
+	/// [is needed](Self::needs_keep_alive), so invoking it until it returns `None` after each
+	/// [`update()`](Self::update) sends all queued data and keeps an idle connection alive.
+	///
+	/// The connection must be in [`Open`](ConnectionStatus::Open) state!
+	pub fn poll_packet(&mut self, now: Instant, buffer: &mut [u8]) -> Result<Option<usize>, BuildPacketError> {
+		if self.status != ConnectionStatus::Open {
+			return Err(BuildPacketError::InvalidState)
+		}
+		if !self.has_queued_data() && !self.needs_keep_alive(now) {
+			return Ok(None)
+		}
+		let len = self.build_packet(buffer)?;
+		// The time of sending is known, so it does not have to wait for the next update.
+		self.activity.last_sent_at = Some(now);
+		self.activity.sent = false;
+		Ok(Some(len))
 	}
 
 	/// Build the next packet that should be sent for this connection.
 	///
+	/// Includes as many queued parcels as fit in the buffer. A packet without any parcels is still
+	/// built, keeping the connection alive.
+	///
+	/// A queued parcel that would not fit even in an empty packet is split into
+	/// [fragments](super::fragment), which are built one per packet by following invocations.
+	/// The fragments must fit in buffers of the same size. A parcel requiring more than
+	/// [`MAX_FRAGMENT_COUNT`](fragment::MAX_FRAGMENT_COUNT) fragments is dropped with
+	/// [`PayloadTooLarge`](BuildPacketError::PayloadTooLarge).
+	///
 	/// The connection must be in [`Open`](ConnectionStatus::Open) state!
 	pub fn build_packet(&mut self, buffer: &mut [u8]) -> Result<usize, BuildPacketError> {
-		todo!()
+		if self.status != ConnectionStatus::Open {
+			return Err(BuildPacketError::InvalidState)
+		}
+		if buffer.len() < size_of::<PacketHeader>() {
+			return Err(BuildPacketError::InsufficientBuffer)
+		}
+
+		let capacity = min(buffer.len() - size_of::<PacketHeader>(), packet::MAX_SEGMENT_BYTE_COUNT);
+		if let Some(fragment) = self.outgoing_fragments.front() {
+			if fragment.len() > capacity {
+				return Err(BuildPacketError::InsufficientBuffer)
+			}
+			let fragment = self.outgoing_fragments.pop_front().unwrap();
+			packet::write_header(buffer, PacketHeader {
+				connection_id: self.connection_id,
+				.. PacketHeader::fragment(fragment.len() as u16)
+			});
+			packet::write_data(buffer, &fragment, 0);
+			self.activity.sent = true;
+			return Ok(size_of::<PacketHeader>() + fragment.len())
+		}
+
+		let data = packet::get_mut_data_segment(buffer);
+		let mut offset = 0;
+		while let Some(parcel) = self.outgoing_volatile_parcels.front() {
+			let byte_count = parcel.byte_count();
+			if byte_count > capacity {
+				if offset > 0 {
+					break
+				}
+				let mut bytes = vec![0; byte_count];
+				parcel.to_bytes(&mut bytes);
+				self.outgoing_volatile_parcels.pop_front();
+				self.outgoing_fragments = fragment::split(self.next_fragment_group, &bytes, capacity)
+					.ok_or(BuildPacketError::PayloadTooLarge)?;
+				self.next_fragment_group = self.next_fragment_group.wrapping_add(1);
+				return self.build_packet(buffer)
+			}
+			if offset + byte_count > capacity {
+				break
+			}
+			parcel.to_bytes(&mut data[offset .. offset + byte_count]);
+			offset += byte_count;
+			self.outgoing_volatile_parcels.pop_front();
+		}
+
+		packet::write_header(buffer, PacketHeader {
+			connection_id: self.connection_id,
+			.. PacketHeader::volatile(offset as u16)
+		});
+		self.activity.sent = true;
+		Ok(size_of::<PacketHeader>() + offset)
+	}
+
+	/// Build a volatile packet that contains only provided parcel, bypassing the parcel queue.
+	///
+	/// Returns the total length of the built packet, the inverse of [`ingest()`](Self::ingest)
+	/// followed by a single [`pop_parcel()`](Self::pop_parcel). Takes `&mut self` only to record
+	/// the packet as sent, so that no [keep-alive](Self::needs_keep_alive) is needed meanwhile.
+	///
+	/// Parcels that serialize to no bytes can not be transmitted and are refused with
+	/// [`SerializationError::UnexpectedValue`](SerializationError::UnexpectedValue), like
+	/// [`push_volatile_parcel()`](Self::push_volatile_parcel) does.
+	///
+	/// The connection must be in [`Open`](ConnectionStatus::Open) state!
+	pub fn write_parcel_packet(&mut self, parcel: &P, buffer: &mut [u8]) -> Result<usize, BuildPacketError> {
+		if self.status != ConnectionStatus::Open {
+			return Err(BuildPacketError::InvalidState)
+		}
+		let byte_count = parcel.byte_count();
+		if byte_count == 0 {
+			return Err(BuildPacketError::Serialization(SerializationError::UnexpectedValue))
+		}
+		if byte_count > packet::MAX_SEGMENT_BYTE_COUNT {
+			return Err(BuildPacketError::PayloadTooLarge)
+		}
+		let packet_len = size_of::<PacketHeader>() + byte_count;
+		if buffer.len() < packet_len {
+			return Err(BuildPacketError::InsufficientBuffer)
+		}
+
+		packet::write_header(buffer, PacketHeader {
+			connection_id: self.connection_id,
+			.. PacketHeader::volatile(byte_count as u16)
+		});
+		parcel.to_bytes(&mut packet::get_mut_data_segment(buffer)[.. byte_count]);
+		self.activity.sent = true;
+		Ok(packet_len)
+	}
+
+	/// Build a packet informing the other end of the connection being closed, with provided code
+	/// and message explaining why.
+	///
+	/// Moves the connection to [`Closed`](ConnectionStatus::Closed) state, discarding queued
+	/// parcels and refusing new ones. The packet is sent only once, so it may be lost, in which
+	/// case the other end eventually considers the connection [`Lost`](ConnectionStatus::Lost).
+	///
+	/// The connection must be in [`Open`](ConnectionStatus::Open) state!
+	pub fn build_close_packet(
+		&mut self,
+		buffer: &mut [u8],
+		code: DisconnectCode,
+		message: &[u8],
+	) -> Result<usize, BuildPacketError> {
+		if self.status != ConnectionStatus::Open {
+			return Err(BuildPacketError::InvalidState)
+		}
+		let payload_len = size_of::<DisconnectCode>() + message.len();
+		if payload_len > packet::MAX_SEGMENT_BYTE_COUNT {
+			return Err(BuildPacketError::PayloadTooLarge)
+		}
+		let packet_len = size_of::<PacketHeader>() + payload_len;
+		if buffer.len() < packet_len {
+			return Err(BuildPacketError::InsufficientBuffer)
+		}
+
+		let mut header = PacketHeader::close_connection(self.connection_id);
+		header.signal.set_parcel_byte_count(payload_len as u16);
+		packet::write_header(buffer, header);
+		packet::write_data(buffer, &code.to_le_bytes(), 0);
+		packet::write_data(buffer, message, size_of::<DisconnectCode>());
+		self.disconnect_reason = Some((code, message.to_vec()));
+		self.close();
+		Ok(packet_len)
+	}
+
+	/// Move the connection to [`Closed`](ConnectionStatus::Closed) state, discarding outgoing
+	/// data that can no longer be sent and partial state that can no longer be completed.
+	fn close(&mut self) {
+		self.status = ConnectionStatus::Closed;
+		self.outgoing_volatile_parcels.clear();
+		self.outgoing_fragments.clear();
+		self.parcel_slots.clear();
+		self.reassembler = Reassembler::default();
 	}
 
 	/// Build a connection-requesting packet that contains provided payload.
 	///
 	/// The connection must be in [`Pending`](ConnectionStatus::Pending) state!
 	pub fn build_request_packet(&mut self, buffer: &mut [u8], payload: &[u8]) -> Result<usize, BuildPacketError> {
-		todo!()
+		if self.status != ConnectionStatus::Pending {
+			return Err(BuildPacketError::InvalidState)
+		}
+		if payload.len() > packet::MAX_SEGMENT_BYTE_COUNT {
+			return Err(BuildPacketError::PayloadTooLarge)
+		}
+		let packet_len = size_of::<PacketHeader>() + payload.len();
+		if buffer.len() < packet_len {
+			return Err(BuildPacketError::InsufficientBuffer)
+		}
+
+		packet::write_header(buffer, PacketHeader::request_connection(self.handshake_id, payload.len() as u16));
+		packet::write_data(buffer, payload, 0);
+		Ok(packet_len)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::byte::ByteSerialize;
+	use crate::connection::error::ReliabilityError;
+
+	/// Build a volatile packet containing provided parcels.
+	fn build_parcel_packet(connection_id: ConnectionId, prelude: DataPrelude, parcels: &[u32]) -> Vec<u8> {
+		let parcel_byte_count = std::mem::size_of_val(parcels);
+		let mut packet = vec![0; size_of::<PacketHeader>() + parcel_byte_count];
+		packet::write_header(&mut packet, PacketHeader {
+			connection_id,
+			prelude,
+			.. PacketHeader::volatile(parcel_byte_count as u16)
+		});
+		for (i, parcel) in parcels.iter().enumerate() {
+			parcel.to_bytes(&mut packet::get_mut_data_segment(&mut packet)[i * size_of::<u32>() ..]);
+		}
+		packet
+	}
+
+	/// Build a response to provided connection request packet.
+	fn build_response_packet(request: &[u8], header: fn(DataPrelude, u16) -> PacketHeader, payload: &[u8]) -> Vec<u8> {
+		let mut packet = vec![0; size_of::<PacketHeader>() + payload.len()];
+		packet::write_header(&mut packet, header(packet::read_header(request).prelude, payload.len() as u16));
+		packet::write_data(&mut packet, payload, 0);
+		packet
+	}
+
+	#[test]
+	fn accepted_context_is_assigned_id() {
+		let mut context = Context::<u32>::pending();
+		let mut request = vec![0; 64];
+		let len = context.build_request_packet(&mut request, b"REQUEST").unwrap();
+		request.truncate(len);
+
+		assert_eq!(context.connection_id(), None);
+
+		let accept = build_response_packet(&request, PacketHeader::accept_connection, &[0x34, 0x12]);
+		let mut foreign_accept = accept.clone();
+		foreign_accept[size_of::<PacketHeader>() - 1] ^= 0xFF;
+
+		assert_eq!(context.ingest_response(&foreign_accept), Err(ConnectionError::MalformedPacket));
+		assert_eq!(context.status(), ConnectionStatus::Pending);
+		assert_eq!(context.ingest_response(&accept), Ok(()));
+		assert_eq!(context.status(), ConnectionStatus::Open);
+		assert_eq!(context.connection_id(), Some(0x1234));
+	}
+
+	#[test]
+	fn duplicate_accept_is_ignored() {
+		let mut context = Context::<u32>::pending();
+		let mut request = vec![0; 64];
+		let len = context.build_request_packet(&mut request, &[]).unwrap();
+		request.truncate(len);
+
+		let accept = build_response_packet(&request, PacketHeader::accept_connection, &[0x34, 0x12]);
+
+		assert_eq!(context.ingest_response(&accept), Ok(()));
+
+		context.ingest(&build_parcel_packet(0x1234, [0; 4], &[10])).unwrap();
+
+		assert_eq!(context.ingest_response(&accept), Ok(()));
+		assert_eq!(context.status(), ConnectionStatus::Open);
+		assert_eq!(context.connection_id(), Some(0x1234));
+		assert_eq!(context.pop_parcel(), Ok((10, [0; 4])));
+
+		let other_accept = build_response_packet(&request, PacketHeader::accept_connection, &[0x35, 0x12]);
+		let reject = build_response_packet(&request, PacketHeader::reject_connection, &[]);
+
+		assert_eq!(context.ingest_response(&other_accept), Err(ConnectionError::InvalidState));
+		assert_eq!(context.ingest_response(&reject), Err(ConnectionError::InvalidState));
+		assert_eq!(context.connection_id(), Some(0x1234));
+	}
+
+	#[test]
+	fn rejected_context_is_closed() {
+		let mut context = Context::<u32>::pending();
+		let mut request = vec![0; 64];
+		let len = context.build_request_packet(&mut request, &[]).unwrap();
+		request.truncate(len);
+
+		let reject = build_response_packet(&request, PacketHeader::reject_connection, &[]);
+
+		assert_eq!(context.ingest_response(&reject), Ok(()));
+		assert_eq!(context.status(), ConnectionStatus::Closed);
+		assert_eq!(context.connection_id(), None);
+	}
+
+	#[test]
+	fn ingested_parcels_pop_in_order() {
+		let mut context = Context::<u32>::accept(1);
+
+		context.ingest(&build_parcel_packet(1, [1, 2, 3, 4], &[10, 20])).unwrap();
+		context.ingest(&build_parcel_packet(1, [5, 6, 7, 8], &[30])).unwrap();
+
+		assert_eq!(context.pop_parcel(), Ok((10, [1, 2, 3, 4])));
+		assert_eq!(context.pop_parcel(), Ok((20, [1, 2, 3, 4])));
+		assert_eq!(context.pop_parcel(), Ok((30, [5, 6, 7, 8])));
+		assert_eq!(context.pop_parcel(), Err(ConnectionError::NoPendingParcels));
+	}
+
+	#[test]
+	fn parcel_count_is_capped() {
+		let mut context = Context::<u32>::accept(1);
+		context.set_max_parcel_count(3);
+
+		assert_eq!(
+			context.ingest(&build_parcel_packet(1, [0; 4], &[1, 2, 3, 4])),
+			Err(ConnectionError::MalformedPacket),
+		);
+		assert_eq!(context.peek_parcel(), Err(ConnectionError::NoPendingParcels));
+
+		context.ingest(&build_parcel_packet(1, [0; 4], &[1, 2, 3])).unwrap();
+
+		assert_eq!(context.incoming_parcels.len(), 3);
+	}
+
+	#[test]
+	fn close_packet_closes_context() {
+		let mut context = Context::<u32>::accept(1);
+		context.ingest(&build_parcel_packet(1, [0; 4], &[10])).unwrap();
+
+		let mut packet = vec![0; size_of::<PacketHeader>()];
+		packet::write_header(&mut packet, PacketHeader::close_connection(1));
+		context.ingest(&packet).unwrap();
+
+		assert_eq!(context.status(), ConnectionStatus::Closed);
+		assert_eq!(context.pop_parcel(), Ok((10, [0; 4])));
+		assert_eq!(context.pop_parcel(), Err(ConnectionError::Closed));
+		assert_eq!(context.peek_parcel(), Err(ConnectionError::Closed));
+		assert_eq!(context.ingest(&packet), Err(ConnectionError::InvalidState));
+	}
+
+	#[test]
+	fn disconnect_reason_reaches_other_end() {
+		let mut sender = Context::<u32>::accept(1);
+		let mut receiver = Context::<u32>::accept(1);
+		let mut buffer = [0; 64];
+
+		assert_eq!(receiver.disconnect_reason(), None);
+
+		let len = sender.build_close_packet(&mut buffer, 3, b"KICKED").unwrap();
+
+		assert_eq!(sender.status(), ConnectionStatus::Closed);
+		assert_eq!(sender.build_close_packet(&mut buffer, 3, b"KICKED"), Err(BuildPacketError::InvalidState));
+		assert_eq!(receiver.ingest(&buffer[.. len]), Ok(()));
+		assert_eq!(receiver.status(), ConnectionStatus::Closed);
+		assert_eq!(receiver.disconnect_reason(), Some((3, &b"KICKED"[..])));
+		assert_eq!(receiver.try_recv(), Err(ConnectionError::Closed));
+
+		let mut receiver = Context::<u32>::accept(1);
+		let mut packet = vec![0; size_of::<PacketHeader>()];
+		packet::write_header(&mut packet, PacketHeader::close_connection(1));
+		receiver.ingest(&packet).unwrap();
+
+		assert_eq!(receiver.disconnect_reason(), Some((NORMAL_DISCONNECT, &[][..])));
+	}
+
+	#[test]
+	fn closed_context_refuses_sends() {
+		let mut sender = Context::<u32>::accept(1);
+		let mut receiver = Context::<u32>::accept(1);
+		let mut buffer = [0; 64];
+		sender.push_volatile_parcel(1).unwrap();
+		receiver.push_volatile_parcel(2).unwrap();
+
+		let len = sender.build_close_packet(&mut buffer, NORMAL_DISCONNECT, &[]).unwrap();
+		receiver.ingest(&buffer[.. len]).unwrap();
+
+		for context in [ &mut sender, &mut receiver, ] {
+			assert!(!context.is_open());
+			assert!(!context.has_queued_data());
+			assert_eq!(context.push_volatile_parcel(3), Err(ConnectionError::InvalidState));
+			assert_eq!(context.build_packet(&mut buffer), Err(BuildPacketError::InvalidState));
+			assert_eq!(context.poll_packet(Instant::now(), &mut buffer), Err(BuildPacketError::InvalidState));
+		}
+	}
+
+	#[test]
+	fn unsupported_reliable_operations_fail() {
+		let mut context = Context::<u32>::accept(1);
+		let mut buffer = [0; 64];
+
+		assert_eq!(context.push_reliable_parcel(1), Err(ConnectionError::InvalidState));
+		assert_eq!(context.write_bytes_to_stream(b"GNET"), Err(ConnectionError::InvalidState));
+		assert_eq!(context.read_from_stream(&mut buffer), Err(ConnectionError::InvalidState));
+		assert!(!context.has_queued_data());
+	}
+
+	#[test]
+	fn keep_alive_interval_changes_at_runtime() {
+		let start = Instant::now();
+		let mut context = Context::<u32>::accept(1);
+		let mut buffer = [0; 64];
+		context.set_keep_alive_interval(Duration::from_secs(1)).unwrap();
+		context.update(start).unwrap();
+
+		assert_eq!(context.poll_packet(start + Duration::from_millis(999), &mut buffer), Ok(None));
+		assert!(context.poll_packet(start + Duration::from_secs(1), &mut buffer).unwrap().is_some());
+
+		context.set_keep_alive_interval(Duration::from_secs(3)).unwrap();
+
+		assert_eq!(context.poll_packet(start + Duration::from_millis(3999), &mut buffer), Ok(None));
+		assert!(context.poll_packet(start + Duration::from_secs(4), &mut buffer).unwrap().is_some());
+
+		assert_eq!(context.set_timeout(Duration::from_secs(4)), Ok(()));
+		assert_eq!(context.keep_alive_interval(), Duration::from_secs(3));
+	}
+
+	#[test]
+	fn invalid_timeouts_are_refused() {
+		let mut context = Context::<u32>::accept(1);
+
+		assert_eq!(context.set_timeout(Duration::ZERO), Err(ConnectionError::InvalidSetting));
+		assert_eq!(context.set_keep_alive_interval(Duration::ZERO), Err(ConnectionError::InvalidSetting));
+		assert_eq!(context.set_keep_alive_interval(DEFAULT_TIMEOUT), Err(ConnectionError::InvalidSetting));
+		assert_eq!(context.timeout(), DEFAULT_TIMEOUT);
+		assert_eq!(context.keep_alive_interval(), DEFAULT_TIMEOUT / 2);
+
+		context.set_keep_alive_interval(Duration::from_secs(3)).unwrap();
+
+		assert_eq!(context.set_timeout(Duration::from_secs(3)), Err(ConnectionError::InvalidSetting));
+		assert_eq!(context.timeout(), DEFAULT_TIMEOUT);
+		assert_eq!(context.keep_alive_interval(), Duration::from_secs(3));
+	}
+
+	#[test]
+	fn silent_connection_is_lost() {
+		let start = Instant::now();
+		let mut context = Context::<u32>::accept(1);
+		let mut buffer = [0; 64];
+		context.set_timeout(Duration::from_secs(4)).unwrap();
+		context.update(start).unwrap();
+
+		assert!(!context.needs_keep_alive(start + Duration::from_secs(1)));
+		assert!(context.needs_keep_alive(start + Duration::from_secs(2)));
+
+		context.ingest(&build_parcel_packet(1, [0; 4], &[])).unwrap();
+		let len = context.build_packet(&mut buffer).unwrap();
+		context.update(start + Duration::from_secs(3)).unwrap();
+
+		assert_eq!(packet::read_header(&buffer[.. len]).signal, packet::SignalBits::keep_alive());
+		assert!(!context.needs_keep_alive(start + Duration::from_secs(4)));
+		assert!(context.needs_keep_alive(start + Duration::from_secs(5)));
+
+		context.update(start + Duration::from_secs(6)).unwrap();
+
+		assert!(context.is_open());
+
+		context.update(start + Duration::from_secs(7)).unwrap();
+
+		assert_eq!(context.status(), ConnectionStatus::Lost);
+		assert!(!context.is_open());
+		assert!(!context.needs_keep_alive(start + Duration::from_secs(8)));
+	}
+
+	#[test]
+	fn rejected_packets_do_not_keep_connection_alive() {
+		let start = Instant::now();
+		let mut context = Context::<u32>::accept(1);
+		context.set_timeout(Duration::from_secs(4)).unwrap();
+		context.update(start).unwrap();
+
+		let mut close = vec![0; size_of::<PacketHeader>() + 1];
+		let mut header = PacketHeader::close_connection(1);
+		header.signal.set_parcel_byte_count(1);
+		packet::write_header(&mut close, header);
+		let mut truncated = build_parcel_packet(1, [0; 4], &[7]);
+		truncated.pop();
+		let mut header = packet::read_header(&truncated);
+		header.signal.set_parcel_byte_count(3);
+		packet::write_header(&mut truncated, header);
+
+		for step in 1 ..= 4 {
+			assert_eq!(context.ingest(&close), Err(ConnectionError::MalformedPacket));
+			assert!(context.ingest(&truncated).is_err());
+			context.update(start + Duration::from_secs(step)).unwrap();
+		}
+
+		assert_eq!(context.status(), ConnectionStatus::Lost);
+	}
+
+	#[test]
+	fn oversized_parcel_is_fragmented() {
+		let mut sender = Context::<Vec<u8>>::accept(5);
+		let mut receiver = Context::<Vec<u8>>::accept(5);
+		let mut buffer = [0; 1200];
+		let large: Vec<u8> = (0 .. 3 * buffer.len()).map(|i| i as u8).collect();
+
+		sender.push_volatile_parcel(large.clone()).unwrap();
+		sender.push_volatile_parcel(vec![1, 2, 3]).unwrap();
+
+		let mut packets = Vec::new();
+		while sender.has_queued_data() {
+			let len = sender.build_packet(&mut buffer).unwrap();
+			packets.push(buffer[.. len].to_vec());
+		}
+
+		assert_eq!(packets.len(), 5);
+
+		// Fragments arrive out of order, the parcel is held until all of them arrive.
+		for packet in packets.iter().rev() {
+			receiver.ingest(packet).unwrap();
+		}
+
+		assert_eq!(receiver.pop_parcel().unwrap().0, vec![1, 2, 3]);
+		assert_eq!(receiver.pop_parcel().unwrap().0, large);
+		assert_eq!(receiver.pop_parcel(), Err(ConnectionError::NoPendingParcels));
+	}
+
+	#[test]
+	fn close_discards_partial_state() {
+		let mut sender = Context::<Vec<u8>>::accept(5);
+		let mut receiver = Context::<Vec<u8>>::accept(5);
+		let mut buffer = [0; 128];
+		sender.push_volatile_parcel(vec![7; 200]).unwrap();
+		receiver.push_deduplicated_parcel(0, vec![1]).unwrap();
+
+		let len = sender.build_packet(&mut buffer).unwrap();
+		receiver.ingest(&buffer[.. len]).unwrap();
+
+		assert_eq!(receiver.reassembler.pending_count(), 1);
+
+		receiver.build_close_packet(&mut buffer, NORMAL_DISCONNECT, &[]).unwrap();
+
+		assert!(receiver.parcel_slots.is_empty());
+		assert_eq!(receiver.reassembler.pending_count(), 0);
+	}
+
+	#[test]
+	fn incomplete_fragmented_parcel_is_dropped() {
+		let start = Instant::now();
+		let mut sender = Context::<Vec<u8>>::accept(5);
+		let mut receiver = Context::<Vec<u8>>::accept(5);
+		let mut buffer = [0; 128];
+		receiver.set_reassembly_timeout(Duration::from_millis(100));
+
+		for _ in 0 .. 2 {
+			sender.push_volatile_parcel(vec![7; 200]).unwrap();
+		}
+		let first_len = sender.build_packet(&mut buffer).unwrap();
+		receiver.ingest(&buffer[.. first_len]).unwrap();
+		let first = buffer[.. first_len].to_vec();
+		assert_eq!(receiver.update(start), Ok(()));
+		assert_eq!(
+			receiver.update(start + Duration::from_millis(100)),
+			Err(ConnectionError::Reliability(ReliabilityError::ReassemblyTimeout)),
+		);
+
+		// The rest of the first parcel arrives too late.
+		while sender.has_queued_data() {
+			let len = sender.build_packet(&mut buffer).unwrap();
+			receiver.ingest(&buffer[.. len]).unwrap();
+		}
+
+		assert_eq!(receiver.pop_parcel().unwrap().0, vec![7; 200]);
+		assert_eq!(receiver.pop_parcel(), Err(ConnectionError::NoPendingParcels));
+
+		assert_eq!(receiver.update(start + Duration::from_millis(200)), Ok(()));
+		assert_eq!(
+			receiver.update(start + Duration::from_millis(300)),
+			Err(ConnectionError::Reliability(ReliabilityError::ReassemblyTimeout)),
+		);
+		receiver.ingest(&first).unwrap();
+
+		assert_eq!(receiver.pop_parcel(), Err(ConnectionError::NoPendingParcels));
+	}
+
+	#[test]
+	fn compact_reclaims_memory() {
+		let mut context = Context::<u32>::accept(1);
+		let burst: Vec<u32> = (0 .. 200).collect();
+		for _ in 0 .. 4 {
+			context.ingest(&build_parcel_packet(1, [0; 4], &burst)).unwrap();
+		}
+		for _ in 0 .. 4 * 200 - 1 {
+			context.pop_parcel().unwrap();
+		}
+		let burst_capacity = context.incoming_parcels.capacity();
+		context.compact();
+
+		assert!(context.incoming_parcels.capacity() < burst_capacity);
+		assert!(context.incoming_parcels.capacity() >= BASELINE_PARCEL_CAPACITY);
+		assert_eq!(context.pop_parcel(), Ok((199, [0; 4])));
+
+		let mut buffer = [0; 1200];
+		for parcel in 0 .. 800 {
+			context.push_volatile_parcel(parcel).unwrap();
+		}
+		while context.outgoing_volatile_parcels.len() > 1 {
+			context.build_packet(&mut buffer[.. size_of::<PacketHeader>() + 4]).unwrap();
+		}
+		let burst_capacity = context.outgoing_volatile_parcels.capacity();
+		context.compact();
+
+		assert!(context.outgoing_volatile_parcels.capacity() < burst_capacity);
+		assert!(context.outgoing_volatile_parcels.capacity() >= BASELINE_PARCEL_CAPACITY);
+		assert_eq!(context.outgoing_volatile_parcels.front(), Some(&799));
+	}
+
+	#[test]
+	fn last_prelude_is_tracked() {
+		let mut context = Context::<u32>::accept(1);
+
+		assert_eq!(context.last_prelude(), None);
+
+		context.ingest(&build_parcel_packet(1, [1, 2, 3, 4], &[10])).unwrap();
+
+		assert_eq!(context.last_prelude(), Some([1, 2, 3, 4]));
+
+		let mut keep_alive = vec![0; size_of::<PacketHeader>()];
+		packet::write_header(&mut keep_alive, PacketHeader {
+			connection_id: 1,
+			prelude: [5, 6, 7, 8],
+			signal: packet::SignalBits::keep_alive(),
+			.. PacketHeader::volatile(0)
+		});
+		context.ingest(&keep_alive).unwrap();
+
+		assert_eq!(context.last_prelude(), Some([5, 6, 7, 8]));
+		assert_eq!(context.pop_parcel(), Ok((10, [1, 2, 3, 4])));
+	}
+
+	#[test]
+	fn try_recv_distinguishes_idle_connection() {
+		let mut context = Context::<u32>::accept(1);
+
+		assert_eq!(context.try_recv(), Ok(None));
+
+		context.ingest(&build_parcel_packet(1, [1, 2, 3, 4], &[10])).unwrap();
+
+		assert_eq!(context.try_recv(), Ok(Some((10, [1, 2, 3, 4]))));
+		assert_eq!(context.try_recv(), Ok(None));
+
+		let mut packet = vec![0; size_of::<PacketHeader>()];
+		packet::write_header(&mut packet, PacketHeader::close_connection(1));
+		context.ingest(&packet).unwrap();
+
+		assert_eq!(context.try_recv(), Err(ConnectionError::Closed));
+	}
+
+	/// Build all packets of the sending context and ingest them by the receiving one.
+	fn transmit(sender: &mut Context<u32>, receiver: &mut Context<u32>, buffer: &mut [u8]) -> usize {
+		let mut packet_count = 0;
+		while !sender.outgoing_volatile_parcels.is_empty() {
+			let len = sender.build_packet(buffer).unwrap();
+			receiver.ingest(&buffer[.. len]).unwrap();
+			packet_count += 1;
+		}
+		packet_count
+	}
+
+	#[test]
+	fn volatile_parcels_are_transmitted() {
+		let mut sender = Context::<u32>::accept(1);
+		let mut receiver = Context::<u32>::accept(1);
+		let mut buffer = [0; size_of::<PacketHeader>() + 10];
+
+		for parcel in 0 .. 5 {
+			sender.push_volatile_parcel(parcel).unwrap();
+		}
+
+		assert_eq!(transmit(&mut sender, &mut receiver, &mut buffer), 3);
+
+		for parcel in 0 .. 5 {
+			assert_eq!(receiver.pop_parcel(), Ok((parcel, [0; 4])));
+		}
+
+		let len = sender.build_packet(&mut buffer).unwrap();
+
+		assert_eq!(len, size_of::<PacketHeader>());
+		assert_eq!(receiver.ingest(&buffer[.. len]), Ok(()));
+		assert_eq!(receiver.try_recv(), Ok(None));
+		assert_eq!(sender.build_packet(&mut buffer[.. 8]), Err(BuildPacketError::InsufficientBuffer));
+		assert_eq!(Context::<u32>::pending().push_volatile_parcel(0), Err(ConnectionError::InvalidState));
+	}
+
+	#[test]
+	fn restored_context_continues_connection() {
+		let mut context = Context::<u32>::pending();
+		let mut buffer = [0; 64];
+		let len = context.build_request_packet(&mut buffer, &[]).unwrap();
+		let accept = build_response_packet(&buffer[.. len], PacketHeader::accept_connection, &[5, 0, 1, 2]);
+
+		let mut restored = Context::<u32>::restore_state(&context.serialize_state()).unwrap();
+
+		assert_eq!(restored.ingest_response(&accept), Ok(()));
+		assert_eq!(context.ingest_response(&accept), Ok(()));
+
+		let mut restored = Context::<u32>::restore_state(&restored.serialize_state()).unwrap();
+
+		assert_eq!(restored.status(), ConnectionStatus::Open);
+		assert_eq!(restored.connection_id(), Some(5));
+		assert_eq!(restored.accept_payload(), &[1, 2]);
+
+		restored.push_volatile_parcel(7).unwrap();
+		let len = restored.build_packet(&mut buffer).unwrap();
+
+		assert_eq!(context.ingest(&buffer[.. len]), Ok(()));
+		assert_eq!(context.pop_parcel(), Ok((7, [0; 4])));
+		assert_eq!(Context::<u32>::restore_state(&[5, 0, 4, 0, 0, 0, 0]).unwrap_err(), SerializationError::UnexpectedValue);
+	}
+
+	#[test]
+	fn packet_of_other_connection_is_rejected() {
+		let mut context = Context::<u32>::accept(1);
+
+		context.ingest(&build_parcel_packet(1, [1; 4], &[10])).unwrap();
+
+		let mut close = vec![0; size_of::<PacketHeader>()];
+		packet::write_header(&mut close, PacketHeader::close_connection(2));
+
+		assert!(packet::is_valid_connected(&close));
+
+		assert_eq!(context.ingest(&build_parcel_packet(2, [2; 4], &[20])), Err(ConnectionError::MalformedPacket));
+		assert_eq!(context.ingest(&close), Err(ConnectionError::MalformedPacket));
+		assert_eq!(context.status(), ConnectionStatus::Open);
+		assert_eq!(context.last_prelude(), Some([1; 4]));
+		assert_eq!(context.pop_parcel(), Ok((10, [1; 4])));
+		assert_eq!(context.try_recv(), Ok(None));
+	}
+
+	#[test]
+	fn parcel_packet_is_read_back() {
+		let mut sender = Context::<u32>::accept(3);
+		let mut receiver = Context::<u32>::accept(3);
+		let mut buffer = [0; 64];
+
+		let len = sender.write_parcel_packet(&0xDEADBEEF, &mut buffer).unwrap();
+
+		assert_eq!(len, size_of::<PacketHeader>() + size_of::<u32>());
+
+		let header = packet::read_header(&buffer);
+
+		assert_eq!(header.connection_id, 3);
+		assert_eq!(header.signal.get_parcel_byte_count(), 4);
+		assert_eq!(receiver.ingest(&buffer[.. len]), Ok(()));
+		assert_eq!(receiver.pop_parcel(), Ok((0xDEADBEEF, [0; 4])));
+		assert_eq!(sender.write_parcel_packet(&0, &mut buffer[.. len - 1]), Err(BuildPacketError::InsufficientBuffer));
+		assert_eq!(Context::<u32>::pending().write_parcel_packet(&0, &mut buffer), Err(BuildPacketError::InvalidState));
+		assert_eq!(
+			Context::<()>::accept(1).write_parcel_packet(&(), &mut buffer),
+			Err(BuildPacketError::Serialization(SerializationError::UnexpectedValue)),
+		);
+	}
+
+	#[test]
+	fn unchanged_parcels_are_deduplicated() {
+		let mut sender = Context::<u32>::accept(1);
+		let mut receiver = Context::<u32>::accept(1);
+		let mut buffer = [0; 1200];
+
+		assert_eq!(sender.push_deduplicated_parcel(0, 1), Ok(true));
+		assert_eq!(sender.push_deduplicated_parcel(1, 1), Ok(true));
+		assert_eq!(sender.push_deduplicated_parcel(0, 1), Ok(false));
+		assert_eq!(sender.push_deduplicated_parcel(0, 2), Ok(true));
+		assert_eq!(sender.push_deduplicated_parcel(0, 2), Ok(false));
+		assert_eq!(sender.push_deduplicated_parcel(1, 1), Ok(false));
+
+		transmit(&mut sender, &mut receiver, &mut buffer);
+
+		assert_eq!(receiver.pop_parcel(), Ok((1, [0; 4])));
+		assert_eq!(receiver.pop_parcel(), Ok((1, [0; 4])));
+		assert_eq!(receiver.pop_parcel(), Ok((2, [0; 4])));
+		assert_eq!(receiver.try_recv(), Ok(None));
+	}
+
+	#[test]
+	fn peek_does_not_consume_parcel() {
+		let mut context = Context::<u32>::accept(1);
+
+		assert_eq!(context.peek_parcel(), Err(ConnectionError::NoPendingParcels));
+
+		context.ingest(&build_parcel_packet(1, [1, 2, 3, 4], &[10, 20])).unwrap();
+
+		assert_eq!(context.peek_parcel(), Ok((&10, [1, 2, 3, 4])));
+		assert_eq!(context.peek_parcel(), Ok((&10, [1, 2, 3, 4])));
+		assert_eq!(context.pop_parcel(), Ok((10, [1, 2, 3, 4])));
+		assert_eq!(context.peek_parcel(), Ok((&20, [1, 2, 3, 4])));
+		assert_eq!(context.pop_parcel(), Ok((20, [1, 2, 3, 4])));
+		assert_eq!(context.peek_parcel(), Err(ConnectionError::NoPendingParcels));
+	}
+
+	#[test]
+	fn partially_deserializable_packet_is_dropped() {
+		let mut context = Context::<u32>::accept(1);
+		let mut packet = build_parcel_packet(1, [0; 4], &[10, 20]);
+		packet.push(0);
+		let mut header = packet::read_header(&packet);
+		header.signal.set_parcel_byte_count(9);
+		packet::write_header(&mut packet, header);
+
+		assert_eq!(
+			context.ingest(&packet),
+			Err(ConnectionError::Serialization(SerializationError::BufferOverflow)),
+		);
+		assert_eq!(context.peek_parcel(), Err(ConnectionError::NoPendingParcels));
 	}
 }
@@ -55,6 +55,23 @@ pub enum ConnectionError {
 	Serialization(SerializationError),
 	/// The connection was in an invalid state.
 	InvalidState,
+	/// A provided setting was not valid for the connection, such as a zero timeout.
+	InvalidSetting,
+	/// A processed packet was not a valid GNet packet.
+	MalformedPacket,
+	/// The connection was closed by the other end.
+	Closed,
+	/// Reliable delivery of data could not be upheld.
+	Reliability(ReliabilityError),
+}
+
+/// A failure to uphold delivery guarantees of reliable data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd)]
+pub enum ReliabilityError {
+	/// Fragments of a split parcel did not all arrive in time to reassemble it.
+	ReassemblyTimeout,
+	/// Too much data arrived ahead of missing data to keep buffering it.
+	OutOfOrderOverflow,
 }
 
 impl From<SerializationError> for ConnectionError {
@@ -68,7 +85,11 @@ impl std::fmt::Display for ConnectionError {
 		match self {
 			ConnectionError::NoPendingParcels => write!(f, "no pending parcels to pop"),
 			ConnectionError::InvalidState => write!(f, "the connection was in an invalid state for given operation"),
+			ConnectionError::InvalidSetting => write!(f, "the provided setting was not valid for the connection"),
+			ConnectionError::MalformedPacket => write!(f, "the processed packet was malformed"),
+			ConnectionError::Closed => write!(f, "the connection was closed by the other end"),
 			ConnectionError::Serialization(error) => error.fmt(f),
+			ConnectionError::Reliability(error) => error.fmt(f),
 		}
 	}
 }
@@ -77,16 +98,36 @@ impl Error for ConnectionError {
 	fn source(&self) -> Option<&(dyn Error + 'static)> {
 		match self {
 			ConnectionError::Serialization(error) => Some(error as &dyn Error),
+			ConnectionError::Reliability(error) => Some(error as &dyn Error),
 			_ => None,
 		}
 	}
 }
 
+impl From<ReliabilityError> for ConnectionError {
+	fn from(error: ReliabilityError) -> Self {
+		Self::Reliability(error)
+	}
+}
+
+impl std::fmt::Display for ReliabilityError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::ReassemblyTimeout => write!(f, "timed out reassembling a fragmented parcel"),
+			Self::OutOfOrderOverflow => write!(f, "too much out of order data is buffered"),
+		}
+	}
+}
+
+impl Error for ReliabilityError {}
+
 /// An error during invocation of [`Context::build_packet`](super::context::Context::build_packet).
 #[derive(Debug, PartialEq, Eq, PartialOrd)]
 pub enum BuildPacketError {
 	/// The provided buffer was too small to build a packet.
 	InsufficientBuffer,
+	/// The provided payload is too large to fit in a single packet.
+	PayloadTooLarge,
 	/// An error during deserialization of a parcel.
 	Serialization(SerializationError),
 	/// The connection was in an invalid state.
@@ -97,11 +138,32 @@ impl std::fmt::Display for BuildPacketError {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
 		match self {
 			Self::InsufficientBuffer => write!(f, "the supplied buffer is too small to hold a useful packet"),
+			Self::PayloadTooLarge => write!(f, "the supplied payload is too large to fit in a packet"),
 			Self::InvalidState => write!(f, "the connection is in a state that does not permit sending packets"),
 			Self::Serialization(error) => {
-				write!(f, "serialization error duing packet building: ");
+				write!(f, "serialization error duing packet building: ")?;
 				error.fmt(f)
 			},
 		}
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn reliability_errors_convert_to_matching_connection_errors() {
+		let errors = [
+			ReliabilityError::ReassemblyTimeout,
+			ReliabilityError::OutOfOrderOverflow,
+		];
+
+		for &error in errors.iter() {
+			let connection_error: ConnectionError = error.into();
+			assert_eq!(connection_error, ConnectionError::Reliability(error));
+			assert_eq!(connection_error.to_string(), error.to_string());
+			assert!(connection_error.source().is_some());
+		}
+	}
+}
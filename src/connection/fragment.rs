@@ -0,0 +1,272 @@
+//! Splitting of parcels too large for a single packet into fragments.
+//!
+//! Each fragment is sent in its own packet with the [`Fragment`](super::packet::Signal::Fragment)
+//! signal set. The parcel segment of such packets starts with a [`FragmentHeader`](FragmentHeader)
+//! followed by the fragment data. The receiving end holds fragments until all of them arrive, or
+//! drops them once the [reassembly timeout](super::context::Context::set_reassembly_timeout)
+//! expires, reporting [`ReassemblyTimeout`](ReliabilityError::ReassemblyTimeout).
+
+use super::error::{ConnectionError, ReliabilityError};
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Default duration an incomplete fragmented parcel is held for, before being dropped.
+pub const DEFAULT_REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Maximum number of fragments a single parcel may be split into.
+pub const MAX_FRAGMENT_COUNT: usize = u8::MAX as usize;
+
+/// Maximum number of incomplete fragmented parcels held at once.
+const MAX_PENDING_PARCEL_COUNT: usize = 32;
+
+/// Identification of a fragment, preceding its data in the parcel segment of a packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentHeader {
+	/// Id shared by all fragments of the same parcel.
+	pub group: u16,
+	/// Index of the fragment within the parcel.
+	pub index: u8,
+	/// Total number of fragments of the parcel.
+	pub count: u8,
+}
+
+impl FragmentHeader {
+	/// Number of bytes of a serialized fragment header.
+	pub const BYTE_COUNT: usize = 4;
+
+	/// Write the header to the start of provided buffer.
+	///
+	/// # Panics
+	/// If the buffer is shorter than [`BYTE_COUNT`](Self::BYTE_COUNT).
+	#[inline]
+	pub fn write(self, bytes: &mut [u8]) {
+		bytes[.. 2].copy_from_slice(&self.group.to_le_bytes());
+		bytes[2] = self.index;
+		bytes[3] = self.count;
+	}
+
+	/// Read a header from the start of provided buffer.
+	///
+	/// Returns `None` if the buffer is too short or the header is not valid.
+	#[inline]
+	pub fn read(bytes: &[u8]) -> Option<Self> {
+		if bytes.len() < Self::BYTE_COUNT {
+			return None
+		}
+		let header = Self {
+			group: u16::from_le_bytes([bytes[0], bytes[1]]),
+			index: bytes[2],
+			count: bytes[3],
+		};
+		if header.index < header.count {
+			Some(header)
+		} else {
+			None
+		}
+	}
+}
+
+/// Split provided serialized parcel into fragments of up to provided byte count, headers included.
+///
+/// Returns `None` if the parcel would require more than [`MAX_FRAGMENT_COUNT`](MAX_FRAGMENT_COUNT)
+/// fragments.
+pub(super) fn split(group: u16, bytes: &[u8], max_fragment_byte_count: usize) -> Option<VecDeque<Vec<u8>>> {
+	let data_byte_count = max_fragment_byte_count.checked_sub(FragmentHeader::BYTE_COUNT).filter(|&x| x > 0)?;
+	let count = bytes.len().div_ceil(data_byte_count);
+	if count > MAX_FRAGMENT_COUNT {
+		return None
+	}
+	let fragments = bytes.chunks(data_byte_count)
+		.enumerate()
+		.map(|(index, data)| {
+			let mut fragment = vec![0; FragmentHeader::BYTE_COUNT + data.len()];
+			FragmentHeader { group, index: index as u8, count: count as u8 }.write(&mut fragment);
+			fragment[FragmentHeader::BYTE_COUNT ..].copy_from_slice(data);
+			fragment
+		})
+		.collect();
+	Some(fragments)
+}
+
+/// Fragments of a parcel that did not all arrive yet.
+#[derive(Debug)]
+struct PartialParcel {
+	fragments: Vec<Option<Vec<u8>>>,
+	missing_count: usize,
+	/// Time of the first [update](Reassembler::update) since the first fragment arrived.
+	started_at: Option<Instant>,
+}
+
+/// Collection of incomplete fragmented parcels.
+#[derive(Debug, Default)]
+pub(super) struct Reassembler {
+	parcels: HashMap<u16, PartialParcel>,
+	/// Number of dropped parcels that were not reported yet.
+	unreported_timeout_count: usize,
+}
+
+impl Reassembler {
+	/// Store provided fragment, returning the serialized parcel if it is complete.
+	///
+	/// Duplicate fragments are ignored. A fragment disagreeing with the others on the number of
+	/// fragments is [`MalformedPacket`](ConnectionError::MalformedPacket).
+	pub(super) fn insert(&mut self, header: FragmentHeader, data: &[u8]) -> Result<Option<Vec<u8>>, ConnectionError> {
+		let count = header.count as usize;
+		if !self.parcels.contains_key(&header.group) && self.parcels.len() == MAX_PENDING_PARCEL_COUNT {
+			return Err(ReliabilityError::OutOfOrderOverflow.into())
+		}
+		let parcel = self.parcels.entry(header.group).or_insert_with(|| PartialParcel {
+			fragments: vec![None; count],
+			missing_count: count,
+			started_at: None,
+		});
+		if parcel.fragments.len() != count {
+			return Err(ConnectionError::MalformedPacket)
+		}
+		let fragment = &mut parcel.fragments[header.index as usize];
+		if fragment.is_some() {
+			return Ok(None)
+		}
+		*fragment = Some(data.to_vec());
+		parcel.missing_count -= 1;
+		if parcel.missing_count > 0 {
+			return Ok(None)
+		}
+
+		let parcel = self.parcels.remove(&header.group).unwrap();
+		Ok(Some(parcel.fragments.into_iter().flatten().flatten().collect()))
+	}
+
+	/// Drop parcels that are incomplete for provided timeout by provided time.
+	///
+	/// Each dropped parcel is reported as a single [`ReassemblyTimeout`](ReliabilityError::ReassemblyTimeout),
+	/// one per call. Parcels that are not reported yet are reported by following calls.
+	pub(super) fn update(&mut self, now: Instant, timeout: Duration) -> Result<(), ReliabilityError> {
+		let previous_count = self.parcels.len();
+		self.parcels.retain(|_, parcel| {
+			let started_at = *parcel.started_at.get_or_insert(now);
+			now.saturating_duration_since(started_at) < timeout
+		});
+		self.unreported_timeout_count += previous_count - self.parcels.len();
+		if self.unreported_timeout_count > 0 {
+			self.unreported_timeout_count -= 1;
+			return Err(ReliabilityError::ReassemblyTimeout)
+		}
+		Ok(())
+	}
+
+	/// Get the number of incomplete parcels.
+	#[cfg(test)]
+	pub(super) fn pending_count(&self) -> usize {
+		self.parcels.len()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn split_fragments_reassemble() {
+		let bytes: Vec<_> = (0 .. 100).collect();
+		let mut fragments = split(7, &bytes, 24).unwrap();
+
+		assert_eq!(fragments.len(), 5);
+		assert!(fragments.iter().all(|fragment| fragment.len() <= 24));
+
+		// Deliver the fragments out of order, with a duplicate.
+		fragments.rotate_left(2);
+		fragments.push_front(fragments[0].clone());
+
+		let mut reassembler = Reassembler::default();
+		let last = fragments.pop_back().unwrap();
+		for fragment in &fragments {
+			let header = FragmentHeader::read(fragment).unwrap();
+
+			assert_eq!(reassembler.insert(header, &fragment[FragmentHeader::BYTE_COUNT ..]), Ok(None));
+		}
+
+		let header = FragmentHeader::read(&last).unwrap();
+
+		assert_eq!(reassembler.insert(header, &last[FragmentHeader::BYTE_COUNT ..]), Ok(Some(bytes)));
+		assert_eq!(reassembler.pending_count(), 0);
+	}
+
+	#[test]
+	fn oversized_parcel_is_not_split() {
+		assert!(split(0, &[0; 256], 5).is_none());
+		assert!(split(0, &[0; 255], 5).is_some());
+		assert!(split(0, &[0; 8], 4).is_none());
+	}
+
+	#[test]
+	fn incomplete_parcels_time_out() {
+		let start = Instant::now();
+		let mut reassembler = Reassembler::default();
+		let header = FragmentHeader { group: 1, index: 0, count: 2 };
+
+		assert_eq!(reassembler.insert(header, &[1]), Ok(None));
+
+		assert_eq!(reassembler.update(start, Duration::from_secs(1)), Ok(()));
+		assert_eq!(reassembler.update(start + Duration::from_millis(999), Duration::from_secs(1)), Ok(()));
+
+		assert_eq!(reassembler.pending_count(), 1);
+		assert_eq!(
+			reassembler.insert(FragmentHeader { count: 3, .. header }, &[2]),
+			Err(ConnectionError::MalformedPacket),
+		);
+
+		assert_eq!(
+			reassembler.update(start + Duration::from_secs(1), Duration::from_secs(1)),
+			Err(ReliabilityError::ReassemblyTimeout),
+		);
+		assert_eq!(reassembler.pending_count(), 0);
+		assert_eq!(reassembler.update(start + Duration::from_secs(2), Duration::from_secs(1)), Ok(()));
+	}
+
+	#[test]
+	fn each_timed_out_parcel_is_reported() {
+		let start = Instant::now();
+		let mut reassembler = Reassembler::default();
+		for group in 0 .. 2 {
+			assert_eq!(reassembler.insert(FragmentHeader { group, index: 0, count: 2 }, &[1]), Ok(None));
+		}
+
+		assert_eq!(reassembler.update(start, Duration::from_secs(1)), Ok(()));
+		assert_eq!(
+			reassembler.update(start + Duration::from_secs(1), Duration::from_secs(1)),
+			Err(ReliabilityError::ReassemblyTimeout),
+		);
+		assert_eq!(
+			reassembler.update(start + Duration::from_secs(1), Duration::from_secs(1)),
+			Err(ReliabilityError::ReassemblyTimeout),
+		);
+		assert_eq!(reassembler.update(start + Duration::from_secs(1), Duration::from_secs(1)), Ok(()));
+	}
+
+	#[test]
+	fn too_many_incomplete_parcels_overflow() {
+		let mut reassembler = Reassembler::default();
+		for group in 0 .. MAX_PENDING_PARCEL_COUNT as u16 {
+			assert_eq!(reassembler.insert(FragmentHeader { group, index: 0, count: 2 }, &[1]), Ok(None));
+		}
+		let header = FragmentHeader { group: MAX_PENDING_PARCEL_COUNT as u16, index: 0, count: 2 };
+
+		assert_eq!(
+			reassembler.insert(header, &[1]),
+			Err(ConnectionError::Reliability(ReliabilityError::OutOfOrderOverflow)),
+		);
+
+		// Fragments of parcels that are already held are still accepted.
+		assert_eq!(reassembler.insert(FragmentHeader { group: 0, index: 1, count: 2 }, &[2]), Ok(Some(vec![1, 2])));
+		assert_eq!(reassembler.insert(header, &[1]), Ok(None));
+	}
+
+	#[test]
+	fn invalid_header_is_refused() {
+		assert_eq!(FragmentHeader::read(&[0, 0, 2, 2]), None);
+		assert_eq!(FragmentHeader::read(&[0, 0, 1]), None);
+		assert_eq!(FragmentHeader::read(&[1, 0, 1, 2]), Some(FragmentHeader { group: 1, index: 1, count: 2 }));
+	}
+}
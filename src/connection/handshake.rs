@@ -0,0 +1,313 @@
+//! Client-side connection handshake.
+
+use super::Parcel;
+use super::context::{Context, ConnectionStatus};
+use super::error::BuildPacketError;
+use super::id::ConnectionId;
+
+use crate::endpoint::{Transmit, TransmitError};
+
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// Interval at which [`block_until_established()`](Handshake::block_until_established) sends the
+/// connection request again while no response arrives.
+pub const REQUEST_RESEND_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Longest pause [`block_until_established()`](Handshake::block_until_established) takes after
+/// the endpoint fails to receive, the pause doubles with every consecutive failure up to it.
+const MAX_ERROR_BACKOFF: Duration = Duration::from_millis(64);
+
+/// State of a client-side connection handshake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeState {
+	/// The connection request was sent, no valid response was received yet.
+	SentRequest,
+	/// The connection was accepted and assigned provided id.
+	Established(ConnectionId),
+	/// The connection was rejected by the other end.
+	Rejected,
+	/// No valid response was received in time.
+	TimedOut,
+}
+
+/// Driver of the client-side connection handshake.
+///
+/// Performs no IO of its own, instead the user sends the built request packet and passes
+/// received responses to [`advance()`](Handshake::advance). All transitions depend only on
+/// provided inputs, which makes the handshake deterministic.
+#[derive(Debug)]
+pub struct Handshake<P: Parcel> {
+	context: Context<P>,
+	request: Vec<u8>,
+	state: HandshakeState,
+	sent_at: Instant,
+	timeout: Duration,
+}
+
+impl<P: Parcel> Handshake<P> {
+	/// Start a new handshake by building a connection-requesting packet with provided payload.
+	///
+	/// The request is assumed to be sent at provided time. Returns the handshake along with the
+	/// length of the built packet.
+	pub fn start(
+		now: Instant,
+		timeout: Duration,
+		buffer: &mut [u8],
+		payload: &[u8],
+	) -> Result<(Self, usize), BuildPacketError> {
+		let mut context = Context::pending();
+		let len = context.build_request_packet(buffer, payload)?;
+		let handshake = Self {
+			context,
+			request: buffer[.. len].to_vec(),
+			state: HandshakeState::SentRequest,
+			sent_at: now,
+			timeout,
+		};
+		Ok((handshake, len))
+	}
+
+	/// Get the current state of the handshake.
+	#[inline]
+	pub fn state(&self) -> HandshakeState {
+		self.state
+	}
+
+	/// Advance the handshake to provided time, processing provided received packet if any.
+	///
+	/// Packets that are not valid responses to the request are ignored. Once the handshake
+	/// reaches a state other than [`SentRequest`](HandshakeState::SentRequest) it no longer changes.
+	pub fn advance(&mut self, now: Instant, incoming: Option<&[u8]>) -> HandshakeState {
+		if self.state != HandshakeState::SentRequest {
+			return self.state
+		}
+		if let Some(packet) = incoming {
+			if self.context.ingest_response(packet).is_ok() {
+				self.state = match self.context.status() {
+					ConnectionStatus::Open => HandshakeState::Established(self.context.connection_id().unwrap()),
+					_ => HandshakeState::Rejected,
+				};
+				return self.state
+			}
+		}
+		if now.saturating_duration_since(self.sent_at) >= self.timeout {
+			self.state = HandshakeState::TimedOut;
+		}
+		self.state
+	}
+
+	/// Block the calling thread until the handshake completes, receiving responses on provided
+	/// endpoint.
+	///
+	/// A convenience for simple synchronous clients, that waits for responses and
+	/// [advances](Self::advance) the handshake until it is established, rejected or times out.
+	/// The request built by [`start()`](Self::start) is sent to provided remote address again
+	/// every [`REQUEST_RESEND_INTERVAL`](REQUEST_RESEND_INTERVAL), in case it or its response
+	/// was lost. Datagrams that are not valid responses, as well as endpoint errors, are ignored.
+	///
+	/// # Note
+	/// The endpoint must honor the timeout of
+	/// [`recv_from_timeout()`](Transmit::recv_from_timeout) for the handshake to time out.
+	pub fn block_until_established<T: Transmit>(
+		mut self,
+		endpoint: &T,
+		remote: SocketAddr,
+	) -> Result<Context<P>, HandshakeState> {
+		let mut buffer = vec![0; endpoint.max_datagram_length()];
+		let deadline = self.sent_at + self.timeout;
+		let mut resend_at = self.sent_at + REQUEST_RESEND_INTERVAL;
+		let mut error_backoff = Duration::from_millis(1);
+		while self.state == HandshakeState::SentRequest {
+			let now = Instant::now();
+			if now >= resend_at {
+				// A failure to send is no different from the request being lost.
+				let _ = endpoint.send_to(&self.request, remote);
+				resend_at = now + REQUEST_RESEND_INTERVAL;
+			}
+			let wait = resend_at.min(deadline).saturating_duration_since(now);
+			match endpoint.recv_from_timeout(&mut buffer, Some(wait)) {
+				Ok((len, _)) => {
+					error_backoff = Duration::from_millis(1);
+					self.advance(Instant::now(), Some(&buffer[.. len]));
+				},
+				Err(TransmitError::NoPendingPackets) => {
+					self.advance(Instant::now(), None);
+				},
+				Err(_) => {
+					std::thread::sleep(error_backoff.min(wait));
+					error_backoff = (error_backoff * 2).min(MAX_ERROR_BACKOFF);
+					self.advance(Instant::now(), None);
+				},
+			}
+		}
+		self.into_context()
+	}
+
+	/// Get the reason the other end provided for [rejecting](HandshakeState::Rejected) the
+	/// connection, which may be empty.
+	#[inline]
+	pub fn reject_reason(&self) -> &[u8] {
+		self.context.reject_reason()
+	}
+
+	/// Get the established connection context.
+	///
+	/// Returns the current state instead if the connection is not
+	/// [established](HandshakeState::Established).
+	pub fn into_context(self) -> Result<Context<P>, HandshakeState> {
+		match self.state {
+			HandshakeState::Established(_) => Ok(self.context),
+			state => Err(state),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::connection::packet;
+	use crate::connection::packet::{DataPrelude, PacketHeader};
+
+	use std::mem::size_of;
+
+	const TIMEOUT: Duration = Duration::from_secs(1);
+
+	fn start(now: Instant) -> (Handshake<()>, Vec<u8>) {
+		let mut request = vec![0; 64];
+		let (handshake, len) = Handshake::start(now, TIMEOUT, &mut request, b"HANDSHAKE").unwrap();
+		request.truncate(len);
+		(handshake, request)
+	}
+
+	fn respond(request: &[u8], header: fn(DataPrelude, u16) -> PacketHeader, payload: &[u8]) -> Vec<u8> {
+		let mut packet = vec![0; size_of::<PacketHeader>() + payload.len()];
+		packet::write_header(&mut packet, header(packet::read_header(request).prelude, payload.len() as u16));
+		packet::write_data(&mut packet, payload, 0);
+		packet
+	}
+
+	#[test]
+	fn handshake_establishes() {
+		let now = Instant::now();
+		let (mut handshake, request) = start(now);
+
+		assert_eq!(handshake.state(), HandshakeState::SentRequest);
+		assert_eq!(handshake.advance(now + TIMEOUT / 2, None), HandshakeState::SentRequest);
+
+		let accept = respond(&request, PacketHeader::accept_connection, &[7, 0]);
+
+		assert_eq!(handshake.advance(now + TIMEOUT / 2, Some(&accept)), HandshakeState::Established(7));
+		assert_eq!(handshake.advance(now + TIMEOUT * 2, None), HandshakeState::Established(7));
+		assert_eq!(handshake.into_context().unwrap().connection_id(), Some(7));
+	}
+
+	#[test]
+	fn handshake_is_rejected() {
+		let now = Instant::now();
+		let (mut handshake, request) = start(now);
+		let reject = respond(&request, PacketHeader::reject_connection, b"SERVER FULL");
+
+		assert_eq!(handshake.advance(now, Some(&reject)), HandshakeState::Rejected);
+		assert_eq!(handshake.reject_reason(), b"SERVER FULL");
+		assert_eq!(handshake.into_context().unwrap_err(), HandshakeState::Rejected);
+	}
+
+	#[test]
+	fn blocking_handshake_establishes() {
+		use crate::connection::listen::{AcceptDecision, ConnectionListener};
+		use crate::endpoint::Open;
+
+		use std::net::{SocketAddr, UdpSocket};
+
+		let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10120));
+		let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10121));
+
+		let mut listener = ConnectionListener::<_, ()>::bind(listener_addr).unwrap();
+		let responder = std::thread::spawn(move || loop {
+			if let Ok((context, _)) = listener.try_accept(|_, _| AcceptDecision::Allow(Vec::new())) {
+				break context.connection_id()
+			}
+			std::thread::yield_now();
+		});
+
+		let client = UdpSocket::open(client_addr).unwrap();
+		let mut request = vec![0; 64];
+		let (handshake, len) = Handshake::<()>::start(Instant::now(), TIMEOUT, &mut request, &[]).unwrap();
+		client.send_to(&request[.. len], listener_addr).unwrap();
+
+		let context = handshake.block_until_established(&client, listener_addr).unwrap();
+
+		assert_eq!(context.connection_id(), responder.join().unwrap());
+	}
+
+	#[test]
+	fn blocking_handshake_resends_lost_request() {
+		use crate::connection::listen::{AcceptDecision, ConnectionListener};
+
+		use std::net::UdpSocket;
+
+		let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10133));
+		let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10134));
+
+		let mut listener = ConnectionListener::<_, ()>::bind(listener_addr).unwrap();
+		let responder = std::thread::spawn(move || loop {
+			if let Ok((context, _)) = listener.try_accept(|_, _| AcceptDecision::Allow(Vec::new())) {
+				break context.connection_id()
+			}
+			std::thread::sleep(Duration::from_millis(1));
+		});
+
+		// A blocking socket, as bound by the standard library.
+		let client = UdpSocket::bind(client_addr).unwrap();
+		let mut request = vec![0; 64];
+		// The first request is never sent, as if it was lost.
+		let (handshake, _) = Handshake::<()>::start(Instant::now(), TIMEOUT, &mut request, &[]).unwrap();
+
+		let context = handshake.block_until_established(&client, listener_addr).unwrap();
+
+		assert_eq!(context.connection_id(), responder.join().unwrap());
+	}
+
+	#[test]
+	fn blocking_handshake_times_out() {
+		use std::net::UdpSocket;
+
+		let server_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10135));
+		let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10136));
+		let timeout = REQUEST_RESEND_INTERVAL * 2 + REQUEST_RESEND_INTERVAL / 2;
+
+		// A server that never responds.
+		let server = UdpSocket::bind(server_addr).unwrap();
+		let client = UdpSocket::bind(client_addr).unwrap();
+		let mut request = vec![0; 64];
+		let start = Instant::now();
+		let (handshake, len) = Handshake::<()>::start(start, timeout, &mut request, &[]).unwrap();
+		client.send_to(&request[.. len], server_addr).unwrap();
+
+		assert_eq!(handshake.block_until_established(&client, server_addr).unwrap_err(), HandshakeState::TimedOut);
+		assert!(start.elapsed() >= timeout);
+
+		let mut buffer = [0; 64];
+		server.set_nonblocking(true).unwrap();
+		for _ in 0 .. 3 {
+			assert_eq!(server.recv_from(&mut buffer).unwrap(), (len, client_addr));
+			assert_eq!(buffer[.. len], request[.. len]);
+		}
+	}
+
+	#[test]
+	fn handshake_times_out() {
+		let now = Instant::now();
+		let (mut handshake, request) = start(now);
+		let mut foreign_accept = respond(&request, PacketHeader::accept_connection, &[7, 0]);
+		foreign_accept[size_of::<PacketHeader>() - 1] ^= 0xFF;
+
+		assert_eq!(handshake.advance(now, Some(&foreign_accept)), HandshakeState::SentRequest);
+		assert_eq!(handshake.advance(now + TIMEOUT, Some(&foreign_accept)), HandshakeState::TimedOut);
+
+		let accept = respond(&request, PacketHeader::accept_connection, &[7, 0]);
+
+		assert_eq!(handshake.advance(now + TIMEOUT, Some(&accept)), HandshakeState::TimedOut);
+	}
+}
@@ -11,7 +11,7 @@ pub struct OutOfIdsError();
 
 /// Manager for [`ConnectionIds`](ConnectionId). Responsible for making sure
 /// there are no [`Connections`](super::connection::Connection) that share ids.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Allocator {
 	/// Largest ConnectionId in use.
 	last_id: ConnectionId,
@@ -34,6 +34,35 @@ impl Allocator {
 		}
 	}
 
+	/// Mark provided [`ConnectionId`](ConnectionId) as used, so it is not assigned by
+	/// [`allocate()`](Self::allocate) until it is [freed](Self::free).
+	///
+	/// Allows pre-reserving ids for specific purposes. Fails if the id is `0` or already in use.
+	///
+	/// Has `O(N)` complexity, where N is the number of elements in `self.free_ids` vector or the
+	/// distance from the largest id in use, whichever is greater.
+	pub fn reserve(&mut self, id: ConnectionId) -> Result<(), OutOfIdsError> {
+		if id == 0 {
+			Err(OutOfIdsError())
+		} else if id > self.last_id {
+			// Ids skipped over are larger than any free ones, so `free_ids` stays sorted.
+			self.free_ids.extend(self.last_id + 1 .. id);
+			self.last_id = id;
+			Ok(())
+		} else if let Ok(pos) = self.free_ids.binary_search(&id) {
+			self.free_ids.remove(pos);
+			Ok(())
+		} else {
+			Err(OutOfIdsError())
+		}
+	}
+
+	/// Get the number of [`ConnectionIds`](ConnectionId) that are currently in use.
+	#[inline]
+	pub fn allocated_count(&self) -> usize {
+		self.last_id as usize - self.free_ids.len()
+	}
+
 	/// Mark provided [`ConnectionId`](ConnectionId) as free to use.
 	/// 
 	/// Has `O(N)` complexity, where N is the number of elements in `self.free_ids` vector.
@@ -50,14 +79,11 @@ impl Allocator {
 			self.free_ids.push(id)
 		}
 	}
-}
 
-impl Default for Allocator {
-	fn default() -> Self {
-		Self {
-			last_id: 0,
-			free_ids: Vec::new(),
-		}
+	/// Iterate over [`ConnectionIds`](ConnectionId) that are currently in use, in ascending order.
+	pub fn allocated(&self) -> impl Iterator<Item = ConnectionId> + '_ {
+		// `free_ids` is kept sorted by `free()`.
+		(1 ..= self.last_id).filter(move |id| self.free_ids.binary_search(id).is_err())
 	}
 }
 
@@ -108,6 +134,47 @@ mod test {
 		assert_eq!(allocator.last_id, 0)
 	}
 
+	#[test]
+	fn allocator_lists_allocated_ids() {
+		let mut allocator = Allocator::default();
+
+		assert_eq!(allocator.allocated().count(), 0);
+
+		for _ in 0 .. 5 {
+			allocator.allocate().unwrap();
+		}
+		allocator.free(4);
+		allocator.free(2);
+		allocator.free(5);
+
+		assert_eq!(allocator.allocated().collect::<Vec<_>>(), [1, 3]);
+	}
+
+	#[test]
+	fn allocator_respects_reserved_ids() {
+		let mut allocator = Allocator::default();
+
+		assert_eq!(allocator.reserve(3), Ok(()));
+		assert_eq!(allocator.reserve(3), Err(OutOfIdsError()));
+		assert_eq!(allocator.reserve(0), Err(OutOfIdsError()));
+		assert_eq!(allocator.allocated_count(), 1);
+
+		let ids: Vec<_> = (0 .. 4).map(|_| allocator.allocate().unwrap()).collect();
+
+		assert!(!ids.contains(&3));
+		assert_eq!(allocator.allocated_count(), 5);
+
+		allocator.free(ids[0]);
+
+		assert_eq!(allocator.reserve(ids[0]), Ok(()));
+		assert_eq!(allocator.allocated().collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+
+		allocator.free(3);
+
+		assert_eq!(allocator.allocated_count(), 4);
+		assert_eq!(allocator.allocate(), Ok(3));
+	}
+
 	#[test]
 	fn allocator_runs_out_of_ids_before_0() {
 		let mut allocator = Allocator::default();
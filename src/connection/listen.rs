@@ -1,46 +1,70 @@
 //! Server-side connection acceptors.
-//!
-//! 
-
-#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
 
 mod accept;
-// #[cfg(test)]
-// pub mod test;
+#[cfg(test)]
+mod test;
 
 pub use accept::*;
 
-use crate::endpoint::{Demux, Transmit, TransmitError, Open,};
+use crate::endpoint::{Demultiplexer, Demux, EndpointStats, Open, Transmit, TransmitError};
+#[cfg(feature = "tokio")]
+use crate::endpoint::AsyncTransmit;
 
-use super::connection::{Connection, ConnectionStatus};
+use super::context::Context;
 use super::id::{ConnectionId, Allocator as ConnectionIdAllocator,};
 use super::packet;
+use super::packet::{DataPrelude, PacketHeader, Signal};
+use super::pool::BufferPool;
 use super::Parcel;
 
-use std::io::Error as IoError;
+use std::collections::{HashMap, VecDeque};
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 use std::marker::PhantomData;
-use std::net::{ToSocketAddrs, SocketAddr,};
-use std::time::Instant;
-use std::sync::{Arc, Mutex,};
+use std::mem::size_of;
+use std::net::{ToSocketAddrs, SocketAddr, UdpSocket,};
+
+/// Default maximum number of datagrams received by a single listener call.
+///
+/// See [`ConnectionListener::set_max_recv_per_tick()`](ConnectionListener::set_max_recv_per_tick).
+pub const DEFAULT_MAX_RECV_PER_TICK: usize = 256;
+
+/// Default maximum number of datagrams buffered for a single connection until they are processed.
+///
+/// See [`ConnectionListener::set_max_datagrams_per_connection()`](ConnectionListener::set_max_datagrams_per_connection).
+pub const DEFAULT_MAX_DATAGRAMS_PER_CONNECTION: usize = 256;
+
+/// Maximum number of bytes in a payload of an [accepting](AcceptDecision::Allow) response.
+pub const MAX_ACCEPT_PAYLOAD_BYTE_COUNT: usize = packet::MAX_SEGMENT_BYTE_COUNT - size_of::<ConnectionId>();
+
+/// Reason of the rejecting response sent when a request is allowed, but the listener ran out of
+/// connection ids to assign.
+pub const OUT_OF_IDS_REJECT_REASON: &[u8] = b"SERVER FULL";
 
 /// A listener passively listens for new connections.
 ///
 /// The new connections are pending, letting the application
 /// decide whether to accept a particular new connection.
 #[derive(Debug)]
-pub struct ConnectionListener<T, P> where
-	T: Transmit,
+pub struct ConnectionListener<E, P> where
+	E: Transmit + Demux<ConnectionId>,
 	P: Parcel,
 {
 	endpoint: E,
 	id_allocator: ConnectionIdAllocator,
 	packet_buffer: Vec<u8>,
+	recv_buffer: Vec<u8>,
 	request_packets: Vec<(usize, SocketAddr)>,
+	accepted_handshakes: HashMap<(SocketAddr, DataPrelude), (ConnectionId, Vec<u8>)>,
+	max_recv_per_tick: usize,
+	max_datagrams_per_connection: usize,
+	deferred_packets: VecDeque<(Vec<u8>, SocketAddr)>,
+	buffer_pool: BufferPool,
+	stats: EndpointStats,
 	_message_type: PhantomData<P>,
 }
 
 impl<E, P> ConnectionListener<E, P> where
-	E: Transmit + Demux<ConnectionId> + Clone,
+	E: Transmit + Demux<ConnectionId>,
 	P: Parcel,
 {
 	// TODO: https://github.com/rust-lang/rust/issues/8995
@@ -48,12 +72,21 @@ impl<E, P> ConnectionListener<E, P> where
 
 	/// Construct a new listener using provided endpoint.
 	#[inline]
-	pub fn new(endpoint: E) -> Self {
+	pub fn new(mut endpoint: E) -> Self {
+		// Connectionless packets are associated with the `0` connection id.
+		endpoint.allow(0);
 		Self {
+			packet_buffer: Vec::with_capacity(endpoint.max_datagram_length()),
+			recv_buffer: vec![0; endpoint.max_datagram_length()],
 			endpoint,
 			id_allocator: Default::default(),
-			packet_buffer: Vec::with_capacity(E::MAX_FRAME_LENGTH),
 			request_packets: Vec::new(),
+			accepted_handshakes: HashMap::new(),
+			max_recv_per_tick: DEFAULT_MAX_RECV_PER_TICK,
+			max_datagrams_per_connection: DEFAULT_MAX_DATAGRAMS_PER_CONNECTION,
+			deferred_packets: VecDeque::new(),
+			buffer_pool: BufferPool::default(),
+			stats: EndpointStats::default(),
 			_message_type: PhantomData,
 		}
 	}
@@ -62,18 +95,27 @@ impl<E, P> ConnectionListener<E, P> where
 	///
 	/// Will pop a single connection request from the endpoint, validate the packet and
 	/// invoke the predicate if the request is valid. If the predicate returns
-	/// [`AcceptDecision::Allow`](AcceptDecision::Allow) the function will return a newly
-	/// established [`Connection`](super::Connection), otherwise it will return
+	/// [`AcceptDecision::Allow`](AcceptDecision::Allow) the function will send an accepting
+	/// packet with the provided payload to the client and return a newly established connection [`Context`](Context)
+	/// along with the address of the client, otherwise it will return
 	/// [`AcceptError::PredicateFail`](AcceptError::PredicateFail).
 	///
 	/// ## Notes
-	/// Does NOT block the calling thread, returning
-	/// [`AcceptError::NoPendingConnections`](AcceptError::NoPendingConnections)
-	/// if there are no pending connections remaining.
+	/// - Does NOT block the calling thread, returning
+	///   [`AcceptError::NoPendingConnections`](AcceptError::NoPendingConnections)
+	///   if there are no pending connections remaining.
+	/// - Requests without a payload are valid, the predicate is invoked with an empty slice.
+	/// - Duplicates of an accepted request from the same address with the same handshake id are
+	///   answered with the same accepting packet without invoking the predicate, returning
+	///   [`AcceptError::DuplicateRequest`](AcceptError::DuplicateRequest), until the connection is
+	///   [closed](ConnectionListener::connection_closed).
+	/// - Responses that could not be sent because the endpoint would block are
+	///   [flushed](ConnectionListener::flush) by following calls.
 	pub fn try_accept<F: FnOnce(SocketAddr, &[u8]) -> AcceptDecision>(
 		&mut self,
 		predicate: F,
-	) -> Result<Connection<E, P>, AcceptError> {
+	) -> Result<(Context<P>, SocketAddr), AcceptError> {
+		self.flush()?;
 		if self.request_packets.is_empty() {
 			self.recv_connectionless_packets()?;
 			if self.request_packets.is_empty() {
@@ -81,37 +123,264 @@ impl<E, P> ConnectionListener<E, P> where
 			}
 		}
 		let (len, src) = self.request_packets.pop().unwrap();
-		let packet = &self.packet_buffer[self.packet_buffer.len() - len ..];
-		match predicate(src, packet::get_parcel_segment(packet)) {
-			AcceptDecision::Allow => {
-				Ok(Connection::opened(
-					self.endpoint.clone(),
-					self.id_allocator.allocate()?,
-					src,
-				))
-			},
-			AcceptDecision::Reject => {
-				todo!("Send reject packet")
-			},
-			AcceptDecision::Ignore => Err(AcceptError::PredicateFail),
+		let packet_start = self.packet_buffer.len() - len;
+		let result = self.process_request(packet_start, src, predicate);
+		self.packet_buffer.truncate(packet_start);
+		result
+	}
+
+	/// Send responses that were deferred because the endpoint would block.
+	///
+	/// Stops without an error if the endpoint would block again, keeping the remaining responses
+	/// for the next flush.
+	pub fn flush(&mut self) -> Result<(), TransmitError> {
+		while let Some((packet, addr)) = self.deferred_packets.front() {
+			match self.endpoint.send_to(packet, *addr) {
+				Ok(_) => {
+					self.stats.bytes_sent += packet.len() as u64;
+					self.stats.datagrams_sent += 1;
+				},
+				Err(error) if error.kind() == IoErrorKind::WouldBlock => return Ok(()),
+				Err(error) => {
+					self.deferred_packets.pop_front();
+					return Err(error.into())
+				},
+			}
+			let (packet, _) = self.deferred_packets.pop_front().unwrap();
+			self.buffer_pool.give(packet);
+		}
+		Ok(())
+	}
+
+	/// Get the endpoint of the listener.
+	///
+	/// Packets of accepted connections may be sent through it, so that they come from the address
+	/// the clients connected to.
+	#[inline]
+	pub fn endpoint(&self) -> &E {
+		&self.endpoint
+	}
+
+	/// Get the pool of buffers used for sending responses.
+	#[inline]
+	pub fn buffer_pool(&self) -> &BufferPool {
+		&self.buffer_pool
+	}
+
+	/// Replace the pool of buffers used for sending responses, returning the previous one.
+	///
+	/// Allows configuring the pool or sharing it between listeners over time.
+	#[inline]
+	pub fn set_buffer_pool(&mut self, pool: BufferPool) -> BufferPool {
+		std::mem::replace(&mut self.buffer_pool, pool)
+	}
+
+	/// Get the statistics of datagrams sent and received by the listener so far.
+	///
+	/// Received datagrams that are not valid packets, packets of connections that are not allowed
+	/// and connectionless packets that are not connection requests are counted as dropped.
+	#[inline]
+	pub fn stats(&self) -> EndpointStats {
+		self.stats
+	}
+
+	/// Set the maximum number of datagrams received from the endpoint by a single call.
+	///
+	/// Bounds the work done by a single [`try_accept()`](ConnectionListener::try_accept) when the
+	/// endpoint is flooded with datagrams, the remaining ones are received by following calls.
+	/// Defaults to [`DEFAULT_MAX_RECV_PER_TICK`](DEFAULT_MAX_RECV_PER_TICK).
+	///
+	/// # Panics
+	/// If provided count is `0`.
+	#[inline]
+	pub fn set_max_recv_per_tick(&mut self, count: usize) {
+		assert!(count > 0, "The listener must be able to receive datagrams!");
+		self.max_recv_per_tick = count;
+	}
+
+	/// Set the maximum number of datagrams buffered for a single connection until they are
+	/// [processed](ConnectionListener::process).
+	///
+	/// Datagrams arriving once the limit is reached are dropped, which bounds the memory used
+	/// by connections that are not processed in time. Connection requests waiting for
+	/// [`try_accept()`](ConnectionListener::try_accept) are limited the same way.
+	/// Defaults to [`DEFAULT_MAX_DATAGRAMS_PER_CONNECTION`](DEFAULT_MAX_DATAGRAMS_PER_CONNECTION).
+	#[inline]
+	pub fn set_max_datagrams_per_connection(&mut self, count: usize) {
+		self.max_datagrams_per_connection = count;
+	}
+
+	/// Receive datagrams pending on the endpoint.
+	///
+	/// Packets of accepted connections are buffered until they are
+	/// [processed](ConnectionListener::process), connection requests until the next
+	/// [`try_accept()`](ConnectionListener::try_accept). Receives at most
+	/// [`max_recv_per_tick`](ConnectionListener::set_max_recv_per_tick) datagrams, returning
+	/// their number.
+	pub fn recv(&mut self) -> Result<usize, TransmitError> {
+		// The receive buffer is only ever overwritten by the endpoint, so it is zeroed just once
+		// instead of on every call.
+		let max_datagram_length = self.endpoint.max_datagram_length();
+		if self.recv_buffer.len() < max_datagram_length {
+			self.recv_buffer.resize(max_datagram_length, 0);
+		}
+		recv_and_demux(
+			&mut self.endpoint,
+			&mut self.recv_buffer[.. max_datagram_length],
+			self.max_recv_per_tick,
+			self.max_datagrams_per_connection,
+			&mut self.stats,
+		)
+	}
+
+	/// Process datagrams buffered for provided accepted connection by invoking provided functor
+	/// with each of them, unbuffering them.
+	///
+	/// Does nothing if the connection was not accepted by the listener or has been closed since.
+	/// The datagrams are usually passed to [`Context::ingest()`](Context::ingest) of the connection.
+	pub fn process<F: FnMut((&[u8], SocketAddr))>(&mut self, connection_id: ConnectionId, functor: F) {
+		if connection_id != 0 && self.endpoint.is_allowed(connection_id) {
+			self.endpoint.process(connection_id, functor);
+		}
+	}
+
+	/// Process datagrams buffered for all active connections by invoking provided functor with
+	/// the id of the connection and each datagram, unbuffering them.
+	///
+	/// Connections are visited in ascending order of their ids.
+	pub fn process_all<F: FnMut(ConnectionId, (&[u8], SocketAddr))>(&mut self, mut functor: F) {
+		for connection_id in self.id_allocator.allocated() {
+			if self.endpoint.is_allowed(connection_id) {
+				self.endpoint.process(connection_id, |dgram| functor(connection_id, dgram));
+			}
 		}
 	}
 
 	/// Inform the listener about a connection that was closed.
-	/// 
+	///
 	/// Note that the connection_id must have been assigned by the listener itself, in other
 	/// words the connection closed must have come from the result of
 	/// [`try_accept()`](ConnectionListener::try_accept).
 	pub fn connection_closed(&mut self, connection_id: ConnectionId) {
 		self.id_allocator.free(connection_id);
 		self.endpoint.block(connection_id);
+		self.accepted_handshakes.retain(|_, (id, _)| *id != connection_id);
+	}
+
+	/// Iterate over ids of connections accepted by the listener that have not been closed since,
+	/// in ascending order.
+	///
+	/// Closed connections must be reported with
+	/// [`connection_closed()`](ConnectionListener::connection_closed).
+	#[inline]
+	pub fn active_ids(&self) -> impl Iterator<Item = ConnectionId> + '_ {
+		self.id_allocator.allocated()
+	}
+
+	/// Validate a buffered request packet and respond to it according to provided predicate.
+	fn process_request<F: FnOnce(SocketAddr, &[u8]) -> AcceptDecision>(
+		&mut self,
+		packet_start: usize,
+		src: SocketAddr,
+		predicate: F,
+	) -> Result<(Context<P>, SocketAddr), AcceptError> {
+		let packet = &self.packet_buffer[packet_start ..];
+		let header = packet::read_header(packet);
+		if !header.signal.is_signal_set(Signal::ConnectionRequest) {
+			self.stats.datagrams_dropped += 1;
+			return Err(AcceptError::InvalidRequest(src))
+		}
+		if let Some((_, accept_payload)) = self.accepted_handshakes.get(&(src, header.prelude)) {
+			// The accepting response was likely lost, answer the same way again.
+			let accept_payload = accept_payload.clone();
+			let accept_header = PacketHeader::accept_connection(header.prelude, accept_payload.len() as u16);
+			self.send_connectionless(accept_header, &accept_payload, src)?;
+			return Err(AcceptError::DuplicateRequest(src))
+		}
+
+		match predicate(src, packet::get_parcel_segment(packet)) {
+			AcceptDecision::Allow(payload) => {
+				if payload.len() > MAX_ACCEPT_PAYLOAD_BYTE_COUNT {
+					return Err(AcceptError::PayloadTooLarge)
+				}
+				let connection_id = match self.id_allocator.allocate() {
+					Ok(connection_id) => connection_id,
+					Err(error) => {
+						// Inform the client instead of letting it time out.
+						let reject_header = PacketHeader::reject_connection(header.prelude, OUT_OF_IDS_REJECT_REASON.len() as u16);
+						self.send_connectionless(reject_header, OUT_OF_IDS_REJECT_REASON, src)?;
+						return Err(error.into())
+					},
+				};
+				let mut accept_payload = Vec::with_capacity(size_of::<ConnectionId>() + payload.len());
+				accept_payload.extend_from_slice(&connection_id.to_le_bytes());
+				accept_payload.extend_from_slice(&payload);
+				let accept_header = PacketHeader::accept_connection(header.prelude, accept_payload.len() as u16);
+				if let Err(error) = self.send_connectionless(accept_header, &accept_payload, src) {
+					self.id_allocator.free(connection_id);
+					return Err(error.into())
+				}
+				self.endpoint.allow(connection_id);
+				self.accepted_handshakes.insert((src, header.prelude), (connection_id, accept_payload));
+				Ok((Context::accept(connection_id), src))
+			},
+			AcceptDecision::Reject => {
+				self.send_connectionless(PacketHeader::reject_connection(header.prelude, 0), &[], src)?;
+				Err(AcceptError::PredicateFail)
+			},
+			AcceptDecision::RejectWithReason(reason) => {
+				if reason.len() > packet::MAX_SEGMENT_BYTE_COUNT {
+					return Err(AcceptError::PayloadTooLarge)
+				}
+				let reject_header = PacketHeader::reject_connection(header.prelude, reason.len() as u16);
+				self.send_connectionless(reject_header, &reason, src)?;
+				Err(AcceptError::PredicateFail)
+			},
+			AcceptDecision::Ignore => Err(AcceptError::PredicateFail),
+		}
+	}
+
+	/// Send a packet not associated with any connection to provided address.
+	///
+	/// If the endpoint would block the packet is deferred until the next [flush](Self::flush).
+	fn send_connectionless(
+		&mut self,
+		header: PacketHeader,
+		payload: &[u8],
+		addr: SocketAddr,
+	) -> Result<(), TransmitError> {
+		let mut packet = self.buffer_pool.take();
+		packet.resize(size_of::<PacketHeader>() + payload.len(), 0);
+		packet::write_header(&mut packet, header);
+		packet::write_data(&mut packet, payload, 0);
+		if !self.deferred_packets.is_empty() {
+			// Preserve the order of responses.
+			self.deferred_packets.push_back((packet, addr));
+			return self.flush()
+		}
+		match self.endpoint.send_to(&packet, addr) {
+			Ok(_) => {
+				self.stats.bytes_sent += packet.len() as u64;
+				self.stats.datagrams_sent += 1;
+				self.buffer_pool.give(packet);
+				Ok(())
+			},
+			Err(error) if error.kind() == IoErrorKind::WouldBlock => {
+				self.deferred_packets.push_back((packet, addr));
+				Ok(())
+			},
+			Err(error) => {
+				self.buffer_pool.give(packet);
+				Err(error.into())
+			},
+		}
 	}
 
 	/// Receive packets on the endpoint and populate packet buffer with connectionless ones.
 	fn recv_connectionless_packets(&mut self) -> Result<(), TransmitError> {
 		assert!(self.request_packets.is_empty());
-		self.packet_buffer.resize(E::MAX_FRAME_LENGTH, 0);
-		recv_filter_and_demux_all(&mut self.endpoint, &mut self.packet_buffer)?;
+		self.recv()?;
+		self.packet_buffer.clear();
 
 		let packet_buffer = &mut self.packet_buffer;
 		let request_packets = &mut self.request_packets;
@@ -127,13 +396,13 @@ impl<E, P> ConnectionListener<E, P> where
 	}
 }
 
-impl<T, D, P> ConnectionListener<Arc<(T, D)>, P> where
+impl<T, D, P> ConnectionListener<(T, D), P> where
 	T: Transmit,
 	D: Demux<ConnectionId>,
 	P: Parcel,
 {
 	/// Create a new `ConnectionListener` using provided [transmitter](Transmit) and default
-	/// [demultiplexer](Demux). 
+	/// [demultiplexer](Demux).
 	pub fn with_transmitter(transmitter: T) -> Self
 	where
 		D: Default,
@@ -157,3 +426,82 @@ impl<T, D, P> ConnectionListener<Arc<(T, D)>, P> where
 		Self::new((transmitter, demultiplexer))
 	}
 }
+
+#[cfg(feature = "tokio")]
+impl<T, D, P> ConnectionListener<(T, D), P> where
+	T: Transmit + AsyncTransmit,
+	D: Demux<ConnectionId>,
+	P: Parcel,
+{
+	/// Accept an incoming connection using provided predicate, waiting for a request to arrive.
+	///
+	/// Behaves like [`try_accept()`](ConnectionListener::try_accept), except that instead of
+	/// returning [`AcceptError::NoPendingConnections`](AcceptError::NoPendingConnections) it awaits
+	/// the transmitter to become readable and tries again.
+	///
+	/// ## Notes
+	/// - The predicate is only invoked once per received request, but may be invoked for
+	///   several rejected requests before a connection is accepted.
+	/// - Deferred responses are only [flushed](ConnectionListener::flush) once a datagram arrives.
+	pub async fn accept<F: FnMut(SocketAddr, &[u8]) -> AcceptDecision>(
+		&mut self,
+		mut predicate: F,
+	) -> Result<(Context<P>, SocketAddr), AcceptError> {
+		loop {
+			match self.try_accept(&mut predicate) {
+				Err(AcceptError::NoPendingConnections) => {
+					AsyncTransmit::readable(&self.endpoint.0).await.map_err(TransmitError::Io)?;
+				},
+				result => return result,
+			}
+		}
+	}
+}
+
+impl<P: Parcel> ConnectionListener<(UdpSocket, Demultiplexer<ConnectionId>), P> {
+	/// Create a new `ConnectionListener` using default [transmitter](Transmit) bound to provided
+	/// address and default [demultiplexer](Demux).
+	#[inline]
+	pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<Self, IoError> {
+		Self::open_with_demultiplexer(addr, Default::default())
+	}
+}
+
+/// Receive up to `max_count` pending datagrams on provided endpoint, buffering valid packets of
+/// allowed connections in the endpoint's demultiplexer.
+///
+/// Packets of connections that already have `max_buffered` datagrams buffered are dropped.
+/// Returns the number of received datagrams, counting them in provided statistics.
+fn recv_and_demux<E>(
+	endpoint: &mut E,
+	buffer: &mut [u8],
+	max_count: usize,
+	max_buffered: usize,
+	stats: &mut EndpointStats,
+) -> Result<usize, TransmitError> where
+	E: Transmit + Demux<ConnectionId>,
+{
+	for count in 0 .. max_count {
+		match endpoint.try_recv_from(buffer) {
+			Ok((len, src)) => {
+				stats.datagrams_received += 1;
+				let packet = &buffer[.. len];
+				if packet::is_valid(packet) {
+					let connection_id = packet::read_connection_id(packet);
+					if endpoint.is_allowed(connection_id)
+						&& endpoint.get_buffered_counts(connection_id).0 < max_buffered
+					{
+						endpoint.push(connection_id, (packet, src));
+					} else {
+						stats.datagrams_dropped += 1;
+					}
+				} else {
+					stats.datagrams_dropped += 1;
+				}
+			},
+			Err(TransmitError::NoPendingPackets) => return Ok(count),
+			Err(error) => return Err(error),
+		}
+	}
+	Ok(max_count)
+}
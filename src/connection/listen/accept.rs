@@ -1,6 +1,6 @@
 //! Data structures used for processing connection requests.
 
-use crate::protocol::id::OutOfIdsError;
+use crate::connection::id::OutOfIdsError;
 
 use super::TransmitError;
 
@@ -15,8 +15,15 @@ pub enum AcceptError {
 	/// There may still be other connections to accept
 	/// Contains the address of the source of the invalid request
 	InvalidRequest(SocketAddr),
-	/// There are no more connection ids to assign.
+	/// There are no more connection ids to assign. The client is sent a rejecting response with
+	/// [`OUT_OF_IDS_REJECT_REASON`](super::OUT_OF_IDS_REJECT_REASON).
 	OutOfIds,
+	/// The payload of the accepting or rejecting response was too large to fit in a single packet.
+	PayloadTooLarge,
+	/// The request duplicates an already accepted one, the accepting response was sent again
+	/// There may still be other connections to accept
+	/// Contains the address of the source of the duplicate request
+	DuplicateRequest(SocketAddr),
 	/// The pending connection failed the provided predicate
 	/// There may still be other connections to accept
 	PredicateFail,
@@ -26,11 +33,18 @@ pub enum AcceptError {
 
 /// A possible result of acceptor function.
 pub enum AcceptDecision {
-	/// Allow the new connection. The [`try_accept()`](ConnectionListener::try_accept)
+	/// Allow the new connection. The [`try_accept()`](super::ConnectionListener::try_accept)
 	/// will return a new connection.
-	Allow,
+	///
+	/// The provided payload (which may be empty) is sent to the client along with the accepting
+	/// response, see [`Context::accept_payload()`](crate::connection::context::Context::accept_payload).
+	Allow(Vec<u8>),
 	/// Actively refuse the new connection, sending a packet informing the client of the decision.
 	Reject,
+	/// Actively refuse the new connection, sending a packet informing the client of the decision
+	/// along with provided reason, see
+	/// [`Context::reject_reason()`](crate::connection::context::Context::reject_reason).
+	RejectWithReason(Vec<u8>),
 	/// Ignore the request. The client will not be informed of the failure to connect.
 	Ignore,
 }
@@ -46,7 +60,7 @@ impl From<TransmitError> for AcceptError {
 }
 
 impl From<OutOfIdsError> for AcceptError {
-	fn from(err: OutOfIdsError) -> Self {
+	fn from(_: OutOfIdsError) -> Self {
 		Self::OutOfIds
 	}
 }
@@ -57,6 +71,8 @@ impl std::fmt::Display for AcceptError {
 			Self::Transmit(error) => error.fmt(f),
 			Self::InvalidRequest(addr) => write!(f, "got incorrect connection request from {}", addr),
 			Self::OutOfIds => write!(f, "ran out of connection ids to assign"),
+			Self::PayloadTooLarge => write!(f, "response payload too large"),
+			Self::DuplicateRequest(addr) => write!(f, "got duplicate connection request from {}", addr),
 			Self::PredicateFail => write!(f, "connection request was denied"),
 			Self::NoPendingConnections => write!(f, "no connections were requested"),
 		}
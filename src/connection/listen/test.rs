@@ -1,60 +1,481 @@
 //! Listener unit tests.
 
-use crate::packet;
-use crate::packet::DataPrelude;
-use crate::connection::{Connection, PendingConnectionError};
 use super::*;
-use std::mem::size_of;
-
-/// Test that a [`ConnectionListener`](ConnectionListener) is able to accept new connections
-/// using provided server and client endpoint implementations.
-pub fn test_accept<S, C>(
-	(listener, listener_addr): (S, SocketAddr),
-	(client, client_addr): (C, SocketAddr),
-) where
-	S: Transmit + Clone,
-	C: Transmit + std::fmt::Debug, 
-{
-	assert_eq!(S::PACKET_BYTE_COUNT, C::PACKET_BYTE_COUNT);
-	assert_eq!(S::RESERVED_BYTE_COUNT, C::RESERVED_BYTE_COUNT);
-	const REQUEST_DATA: &[u8] = b"GNET CONNECTION REQUEST";
-
-	let server = ConnectionListener::<S, ()>::new(listener);
-	let client_con = Connection::<C, ()>::connect(client, listener_addr, REQUEST_DATA.to_vec())
-		.expect("Failed to begin establishing client connection!");
-
-	let accept_result = server.try_accept(|addr, payload| -> AcceptDecision {
-		if addr == client_addr && payload == REQUEST_DATA {
-			AcceptDecision::Allow
+use crate::connection::context::ConnectionStatus;
+
+use std::cell::Cell;
+use std::net::UdpSocket;
+
+const REQUEST_PAYLOAD: &[u8] = b"GNET CONNECTION REQUEST";
+
+/// Send a connection request with provided payload from the client socket to the listener.
+fn request_connection(client: &UdpSocket, listener_addr: SocketAddr, payload: &[u8]) -> Context<()> {
+	let mut context = Context::pending();
+	let mut buffer = [0; 1200];
+	let len = context.build_request_packet(&mut buffer, payload).unwrap();
+	client.send_to(&buffer[.. len], listener_addr).unwrap();
+	context
+}
+
+/// Receive a single connectionless packet on the client socket and return its header.
+fn recv_response(client: &UdpSocket) -> PacketHeader {
+	let mut buffer = [0; 1200];
+	let (len, _) = client.recv_from(&mut buffer).unwrap();
+	assert!(packet::is_valid_connectionless(&buffer[.. len]));
+	packet::read_header(&buffer[.. len])
+}
+
+#[test]
+fn listener_binds_and_accepts() {
+	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10100));
+	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10101));
+
+	let mut listener = ConnectionListener::<_, ()>::bind(listener_addr).unwrap();
+	let client = UdpSocket::bind(client_addr).unwrap();
+	request_connection(&client, listener_addr, REQUEST_PAYLOAD);
+
+	let (context, addr) = listener.try_accept(|addr, payload| {
+		if addr == client_addr && payload == REQUEST_PAYLOAD {
+			AcceptDecision::Allow(Vec::new())
 		} else {
 			AcceptDecision::Reject
 		}
+	}).expect("Failed to accept a connection!");
+
+	assert_eq!(addr, client_addr);
+	assert_eq!(context.status(), ConnectionStatus::Open);
+	assert!(context.connection_id().is_some());
+	assert!(recv_response(&client).signal.is_signal_set(Signal::ConnectionAccepted));
+	assert_eq!(listener.try_accept(|_, _| AcceptDecision::Allow(Vec::new())).unwrap_err(), AcceptError::NoPendingConnections);
+}
+
+#[test]
+fn listener_rejects() {
+	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10102));
+	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10103));
+
+	let mut listener = ConnectionListener::<_, ()>::bind(listener_addr).unwrap();
+	let client = UdpSocket::bind(client_addr).unwrap();
+	request_connection(&client, listener_addr, &[]);
+
+	let accept_result = listener.try_accept(|_, _| AcceptDecision::Reject);
+
+	assert_eq!(accept_result.unwrap_err(), AcceptError::PredicateFail);
+	assert!(recv_response(&client).signal.is_signal_set(Signal::ConnectionClosed));
+}
+
+#[test]
+fn listener_accepts_empty_request() {
+	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10104));
+	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10105));
+
+	let mut listener = ConnectionListener::<_, ()>::bind(listener_addr).unwrap();
+	let client = UdpSocket::bind(client_addr).unwrap();
+	request_connection(&client, listener_addr, &[]);
+
+	let (context, _) = listener.try_accept(|_, payload| {
+		if payload.is_empty() {
+			AcceptDecision::Allow(Vec::new())
+		} else {
+			AcceptDecision::Reject
+		}
+	}).expect("Failed to accept an empty request!");
+
+	assert_eq!(context.status(), ConnectionStatus::Open);
+	assert!(recv_response(&client).signal.is_signal_set(Signal::ConnectionAccepted));
+}
+
+#[test]
+fn listener_lists_active_connections() {
+	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10106));
+	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10107));
+
+	let mut listener = ConnectionListener::<_, ()>::bind(listener_addr).unwrap();
+	let client = UdpSocket::bind(client_addr).unwrap();
+
+	let mut ids = Vec::new();
+	for _ in 0 .. 3 {
+		request_connection(&client, listener_addr, REQUEST_PAYLOAD);
+		let (context, _) = listener.try_accept(|_, _| AcceptDecision::Allow(Vec::new())).unwrap();
+		ids.push(context.connection_id().unwrap());
+	}
+	ids.sort_unstable();
+
+	assert_eq!(listener.active_ids().collect::<Vec<_>>(), ids);
+
+	listener.connection_closed(ids[1]);
+	ids.remove(1);
+
+	assert_eq!(listener.active_ids().collect::<Vec<_>>(), ids);
+}
+
+#[test]
+fn listener_receives_limited_datagrams() {
+	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10108));
+	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10109));
+
+	let mut endpoint = <(UdpSocket, Demultiplexer<ConnectionId>)>::open(listener_addr).unwrap();
+	endpoint.allow(0);
+	let client = UdpSocket::bind(client_addr).unwrap();
+	for _ in 0 .. 5 {
+		request_connection(&client, listener_addr, REQUEST_PAYLOAD);
+	}
+
+	let mut buffer = vec![0; endpoint.max_datagram_length()];
+	let mut stats = EndpointStats::default();
+
+	assert_eq!(recv_and_demux(&mut endpoint, &mut buffer, 2, DEFAULT_MAX_DATAGRAMS_PER_CONNECTION, &mut stats), Ok(2));
+	assert_eq!(endpoint.get_buffered_counts(0).0, 2);
+	assert_eq!(recv_and_demux(&mut endpoint, &mut buffer, 2, DEFAULT_MAX_DATAGRAMS_PER_CONNECTION, &mut stats), Ok(2));
+	assert_eq!(recv_and_demux(&mut endpoint, &mut buffer, 2, DEFAULT_MAX_DATAGRAMS_PER_CONNECTION, &mut stats), Ok(1));
+	assert_eq!(endpoint.get_buffered_counts(0).0, 5);
+	assert_eq!(stats.datagrams_received, 5);
+}
+
+/// Transmitter that reports the send buffer being full on the first send.
+struct FullOnceTransmit {
+	socket: UdpSocket,
+	is_full: Cell<bool>,
+}
+
+impl Transmit for FullOnceTransmit {
+	fn max_datagram_length(&self) -> usize {
+		self.socket.max_datagram_length()
+	}
+
+	fn send_to(&self, data: &[u8], addr: SocketAddr) -> Result<usize, IoError> {
+		if self.is_full.replace(false) {
+			Err(IoErrorKind::WouldBlock.into())
+		} else {
+			Transmit::send_to(&self.socket, data, addr)
+		}
+	}
+
+	fn try_recv_from(&self, buffer: &mut [u8]) -> Result<(usize, SocketAddr), TransmitError> {
+		self.socket.try_recv_from(buffer)
+	}
+}
+
+#[test]
+fn listener_defers_blocked_responses() {
+	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10110));
+	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10111));
+
+	let transmitter = FullOnceTransmit { socket: UdpSocket::open(listener_addr).unwrap(), is_full: Cell::new(true) };
+	let mut listener = ConnectionListener::<(_, Demultiplexer<_>), ()>::with_transmitter(transmitter);
+	let client = UdpSocket::bind(client_addr).unwrap();
+	request_connection(&client, listener_addr, REQUEST_PAYLOAD);
+
+	let (context, _) = listener.try_accept(|_, _| AcceptDecision::Allow(Vec::new())).unwrap();
+
+	client.set_nonblocking(true).unwrap();
+	assert_eq!(client.recv_from(&mut [0; 64]).unwrap_err().kind(), IoErrorKind::WouldBlock);
+	client.set_nonblocking(false).unwrap();
+
+	listener.flush().unwrap();
+	let header = recv_response(&client);
+
+	assert!(header.signal.is_signal_set(Signal::ConnectionAccepted));
+	assert_eq!(context.status(), ConnectionStatus::Open);
+}
+
+#[test]
+fn accept_payload_reaches_client() {
+	const ACCEPT_PAYLOAD: &[u8] = b"SPAWN POINT";
+
+	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10112));
+	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10113));
+
+	let mut listener = ConnectionListener::<_, ()>::bind(listener_addr).unwrap();
+	let client = UdpSocket::bind(client_addr).unwrap();
+	let mut client_context = request_connection(&client, listener_addr, REQUEST_PAYLOAD);
+
+	let (context, _) = listener.try_accept(|_, _| AcceptDecision::Allow(ACCEPT_PAYLOAD.to_vec())).unwrap();
+
+	let mut buffer = [0; 1200];
+	let (len, _) = client.recv_from(&mut buffer).unwrap();
+	client_context.ingest_response(&buffer[.. len]).unwrap();
+
+	assert_eq!(client_context.connection_id(), context.connection_id());
+	assert_eq!(client_context.accept_payload(), ACCEPT_PAYLOAD);
+
+	request_connection(&client, listener_addr, REQUEST_PAYLOAD);
+	let oversized_payload = vec![0; MAX_ACCEPT_PAYLOAD_BYTE_COUNT + 1];
+
+	assert_eq!(
+		listener.try_accept(|_, _| AcceptDecision::Allow(oversized_payload)).unwrap_err(),
+		AcceptError::PayloadTooLarge,
+	);
+}
+
+#[test]
+fn reject_reason_reaches_client() {
+	const REJECT_REASON: &[u8] = b"SERVER FULL";
+
+	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10116));
+	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10117));
+
+	let mut listener = ConnectionListener::<_, ()>::bind(listener_addr).unwrap();
+	let client = UdpSocket::bind(client_addr).unwrap();
+	let mut client_context = request_connection(&client, listener_addr, REQUEST_PAYLOAD);
+
+	let accept_result = listener.try_accept(|_, _| AcceptDecision::RejectWithReason(REJECT_REASON.to_vec()));
+
+	assert_eq!(accept_result.unwrap_err(), AcceptError::PredicateFail);
+
+	let mut buffer = [0; 1200];
+	let (len, _) = client.recv_from(&mut buffer).unwrap();
+	client_context.ingest_response(&buffer[.. len]).unwrap();
+
+	assert_eq!(client_context.status(), ConnectionStatus::Closed);
+	assert_eq!(client_context.reject_reason(), REJECT_REASON);
+}
+
+#[test]
+fn listener_reuses_response_buffers() {
+	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10118));
+	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10119));
+
+	let mut listener = ConnectionListener::<_, ()>::bind(listener_addr).unwrap();
+	let client = UdpSocket::bind(client_addr).unwrap();
+
+	for _ in 0 .. 5 {
+		request_connection(&client, listener_addr, REQUEST_PAYLOAD);
+		assert_eq!(listener.try_accept(|_, _| AcceptDecision::Reject).unwrap_err(), AcceptError::PredicateFail);
+		recv_response(&client);
+	}
+
+	assert_eq!(listener.buffer_pool().allocation_count(), 1);
+	assert_eq!(listener.buffer_pool().pooled_count(), 1);
+}
+
+#[test]
+fn listener_out_of_ids_rejects() {
+	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10122));
+	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10123));
+
+	let mut listener = ConnectionListener::<_, ()>::bind(listener_addr).unwrap();
+	let client = UdpSocket::bind(client_addr).unwrap();
+	while listener.id_allocator.allocate().is_ok() {}
+	let mut client_context = request_connection(&client, listener_addr, REQUEST_PAYLOAD);
+
+	let accept_result = listener.try_accept(|_, _| AcceptDecision::Allow(Vec::new()));
+
+	assert_eq!(accept_result.unwrap_err(), AcceptError::OutOfIds);
+
+	let mut buffer = [0; 1200];
+	let (len, _) = client.recv_from(&mut buffer).unwrap();
+	client_context.ingest_response(&buffer[.. len]).unwrap();
+
+	assert_eq!(client_context.status(), ConnectionStatus::Closed);
+	assert_eq!(client_context.reject_reason(), OUT_OF_IDS_REJECT_REASON);
+}
+
+#[test]
+fn listener_reuses_receive_buffer() {
+	const LONG_PAYLOAD: &[u8] = b"GNET CONNECTION REQUEST WITH A LONGER PAYLOAD";
+
+	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10114));
+	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10115));
+
+	let mut listener = ConnectionListener::<_, ()>::bind(listener_addr).unwrap();
+	let client = UdpSocket::bind(client_addr).unwrap();
+
+	for &payload in [LONG_PAYLOAD, REQUEST_PAYLOAD, &[]].iter() {
+		request_connection(&client, listener_addr, payload);
+		let accept_result = listener.try_accept(|_, received| {
+			assert_eq!(received, payload);
+			AcceptDecision::Ignore
+		});
+
+		assert_eq!(accept_result.unwrap_err(), AcceptError::PredicateFail);
+	}
+}
+
+#[test]
+fn listener_counts_dropped_datagrams() {
+	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10126));
+	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10127));
+
+	let mut listener = ConnectionListener::<_, ()>::bind(listener_addr).unwrap();
+	let client = UdpSocket::bind(client_addr).unwrap();
+	client.send_to(&[0; size_of::<PacketHeader>() - 1], listener_addr).unwrap();
+	// A valid packet of a connection the listener did not accept.
+	let mut packet = vec![0; size_of::<PacketHeader>()];
+	packet::write_header(&mut packet, PacketHeader { connection_id: 7, .. PacketHeader::volatile(0) });
+	assert!(packet::is_valid(&packet));
+	client.send_to(&packet, listener_addr).unwrap();
+	request_connection(&client, listener_addr, &[]);
+
+	let accepted = loop {
+		match listener.try_accept(|_, _| AcceptDecision::Allow(Vec::new())) {
+			Err(AcceptError::NoPendingConnections) => std::thread::sleep(std::time::Duration::from_millis(1)),
+			result => break result,
+		}
+	};
+
+	assert!(accepted.is_ok());
+	assert_eq!(listener.try_accept(|_, _| AcceptDecision::Allow(Vec::new())).unwrap_err(), AcceptError::NoPendingConnections);
+	assert_eq!(listener.stats(), EndpointStats {
+		bytes_sent: (size_of::<PacketHeader>() + size_of::<ConnectionId>()) as u64,
+		datagrams_sent: 1,
+		datagrams_received: 3,
+		datagrams_dropped: 2,
 	});
+}
+
+#[test]
+fn listener_buffers_connection_datagrams() {
+	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10129));
+	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10130));
 
-	accept_result.expect("Failed to accept a connection!");
-	client_con.try_promote().expect("Failed to promote client connection!");
+	let mut listener = ConnectionListener::<_, ()>::bind(listener_addr).unwrap();
+	listener.set_max_datagrams_per_connection(2);
+	let client = UdpSocket::bind(client_addr).unwrap();
+	request_connection(&client, listener_addr, REQUEST_PAYLOAD);
+	let (context, _) = loop {
+		match listener.try_accept(|_, _| AcceptDecision::Allow(Vec::new())) {
+			Err(AcceptError::NoPendingConnections) => std::thread::sleep(std::time::Duration::from_millis(1)),
+			result => break result.expect("Failed to accept a connection!"),
+		}
+	};
+	let connection_id = context.connection_id().unwrap();
+
+	let mut packet = vec![0; size_of::<PacketHeader>()];
+	packet::write_header(&mut packet, PacketHeader { connection_id, .. PacketHeader::volatile(0) });
+	for _ in 0 .. 3 {
+		client.send_to(&packet, listener_addr).unwrap();
+	}
+	let mut received = 0;
+	while received < 3 {
+		received += listener.recv().unwrap();
+	}
+
+	let mut processed = Vec::new();
+	listener.process(connection_id, |(dgram, src)| processed.push((dgram.to_vec(), src)));
+
+	assert_eq!(processed, vec![(packet.clone(), client_addr); 2]);
+	assert_eq!(listener.stats().datagrams_dropped, 1);
+
+	client.send_to(&packet, listener_addr).unwrap();
+	while listener.recv().unwrap() == 0 {}
+	let mut processed = Vec::new();
+	listener.process_all(|id, (dgram, _)| processed.push((id, dgram.len())));
+
+	assert_eq!(processed, vec![(connection_id, packet.len())]);
+
+	listener.connection_closed(connection_id);
+	listener.process(connection_id, |_| panic!("Closed connections have no datagrams!"));
 }
 
-/// Test that a [`ConnectionListener`](ConnectionListener) is able to deny new connections
-/// using provided server and client endpoint implementations.
-pub fn test_deny<S, C>(
-	(listener, listener_addr): (S, SocketAddr),
-	(client, client_addr): (C, SocketAddr),
-) where
-	S: Transmit + Clone + std::fmt::Debug,
-	C: Transmit + std::fmt::Debug,
-{
-	assert_eq!(S::PACKET_BYTE_COUNT, C::PACKET_BYTE_COUNT);
-	assert_eq!(S::RESERVED_BYTE_COUNT, C::RESERVED_BYTE_COUNT);
+#[test]
+fn listener_answers_duplicate_requests() {
+	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10131));
+	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10132));
+
+	let mut listener = ConnectionListener::<_, ()>::bind(listener_addr).unwrap();
+	let client = UdpSocket::bind(client_addr).unwrap();
+	let mut context = Context::<()>::pending();
+	let mut request = [0; 1200];
+	let request_len = context.build_request_packet(&mut request, REQUEST_PAYLOAD).unwrap();
+	let accept = |listener: &mut ConnectionListener<_, ()>, predicate_calls: &mut usize| loop {
+		match listener.try_accept(|_, _| {
+			*predicate_calls += 1;
+			AcceptDecision::Allow(b"WELCOME".to_vec())
+		}) {
+			Err(AcceptError::NoPendingConnections) => std::thread::sleep(std::time::Duration::from_millis(1)),
+			result => break result,
+		}
+	};
+	let mut predicate_calls = 0;
+
+	client.send_to(&request[.. request_len], listener_addr).unwrap();
+	let (accepted, _) = accept(&mut listener, &mut predicate_calls).expect("Failed to accept a connection!");
+	client.send_to(&request[.. request_len], listener_addr).unwrap();
+
+	assert_eq!(accept(&mut listener, &mut predicate_calls).unwrap_err(), AcceptError::DuplicateRequest(client_addr));
+	assert_eq!(predicate_calls, 1);
+
+	let mut responses = [[0; 1200]; 2];
+	for response in responses.iter_mut() {
+		client.recv_from(response).unwrap();
+	}
 
-	let server = ConnectionListener::<S, ()>::new(listener);
-	let client_con = Connection::<C, ()>::connect(client, listener_addr, vec![])
-		.expect("Failed to begin establishing client connection!");
+	assert_eq!(responses[0][..], responses[1][..]);
 
-	let accept_result = server.try_accept(|_, _| -> AcceptDecision {
-		AcceptDecision::Reject
+	let len = size_of::<PacketHeader>() + size_of::<ConnectionId>() + b"WELCOME".len();
+	context.ingest_response(&responses[1][.. len]).unwrap();
+
+	assert_eq!(context.connection_id(), accepted.connection_id());
+	assert_eq!(listener.active_ids().count(), 1);
+
+	listener.connection_closed(accepted.connection_id().unwrap());
+	client.send_to(&request[.. request_len], listener_addr).unwrap();
+
+	assert!(accept(&mut listener, &mut predicate_calls).is_ok());
+	assert_eq!(predicate_calls, 2);
+}
+
+#[test]
+fn listener_accepts_over_ipv6() {
+	let listener_addr = SocketAddr::from(([ 0, 0, 0, 0, 0, 0, 0, 1, ], 10128));
+
+	let mut listener = ConnectionListener::<_, ()>::bind(listener_addr).unwrap();
+	let client = UdpSocket::open_for_remote(listener_addr).unwrap();
+	client.set_nonblocking(false).unwrap();
+
+	assert!(client.local_addr().unwrap().is_ipv6());
+
+	let mut context = request_connection(&client, listener_addr, REQUEST_PAYLOAD);
+	let (accepted, addr) = loop {
+		match listener.try_accept(|_, _| AcceptDecision::Allow(Vec::new())) {
+			Err(AcceptError::NoPendingConnections) => std::thread::sleep(std::time::Duration::from_millis(1)),
+			result => break result.expect("Failed to accept a connection!"),
+		}
+	};
+	let mut buffer = [0; 1200];
+	let (len, _) = client.recv_from(&mut buffer).unwrap();
+	context.ingest_response(&buffer[.. len]).unwrap();
+
+	assert_eq!(addr.port(), client.local_addr().unwrap().port());
+	assert_eq!(context.status(), ConnectionStatus::Open);
+	assert_eq!(context.connection_id(), accepted.connection_id());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn listener_accepts_asynchronously() {
+	use crate::endpoint::AsyncTransmit;
+
+	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10124));
+	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10125));
+
+	let mut listener = ConnectionListener::<(tokio::net::UdpSocket, Demultiplexer<_>), ()>::open_with_demultiplexer(
+		listener_addr,
+		Default::default(),
+	).unwrap();
+	let client = tokio::net::UdpSocket::bind(client_addr).await.unwrap();
+
+	let mut context = Context::<()>::pending();
+	let mut buffer = [0; 1200];
+	let client_task = async {
+		let len = context.build_request_packet(&mut buffer, REQUEST_PAYLOAD).unwrap();
+		AsyncTransmit::send_to(&client, &buffer[.. len], listener_addr).await.unwrap();
+		let (len, _) = AsyncTransmit::recv_from(&client, &mut buffer).await.unwrap();
+		context.ingest_response(&buffer[.. len]).unwrap();
+	};
+	let listener_task = listener.accept(|_, payload| {
+		if payload == REQUEST_PAYLOAD {
+			AcceptDecision::Allow(Vec::new())
+		} else {
+			AcceptDecision::Reject
+		}
 	});
+	let ((), accepted) = tokio::join!(client_task, listener_task);
+	let (accepted, addr) = accepted.expect("Failed to accept a connection!");
 
-	assert_eq!(accept_result, Err(AcceptError::PredicateFail));
-	assert_eq!(client_con.try_promote(), Err(PendingConnectionError::Rejected));
-}
\ No newline at end of file
+	assert_eq!(addr, client_addr);
+	assert_eq!(accepted.status(), ConnectionStatus::Open);
+	assert_eq!(context.status(), ConnectionStatus::Open);
+	assert_eq!(context.connection_id(), accepted.connection_id());
+}
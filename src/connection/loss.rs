@@ -0,0 +1,99 @@
+//! Packet loss estimation of a connection.
+
+use std::collections::VecDeque;
+
+/// Default number of most recently sent packets [`PacketLoss`](PacketLoss) is computed over.
+pub const DEFAULT_PACKET_LOSS_WINDOW: usize = 256;
+
+/// Estimator of the ratio of lost packets of a connection.
+///
+/// Records whether each sent reliable packet had to be retransmitted and computes the ratio of
+/// retransmitted ones over a sliding window of the most recently sent packets. Applications may
+/// use it to reduce the rate of sending under congestion.
+#[derive(Debug, Clone)]
+pub struct PacketLoss {
+	window: usize,
+	packets: VecDeque<bool>,
+	retransmitted_count: usize,
+}
+
+impl PacketLoss {
+	/// Construct a new estimator computing the ratio over provided number of packets.
+	///
+	/// # Panics
+	/// If the window is empty.
+	pub fn new(window: usize) -> Self {
+		assert!(window > 0, "Packet loss window must not be empty!");
+		Self {
+			window,
+			packets: VecDeque::with_capacity(window),
+			retransmitted_count: 0,
+		}
+	}
+
+	/// Get the number of packets the estimator computes the ratio over.
+	#[inline]
+	pub fn window(&self) -> usize {
+		self.window
+	}
+
+	/// Record a packet being sent, and whether it is a retransmission of a lost one.
+	///
+	/// Discards the oldest record once the window is full.
+	pub fn record_sent(&mut self, retransmitted: bool) {
+		if self.packets.len() == self.window && self.packets.pop_front() == Some(true) {
+			self.retransmitted_count -= 1;
+		}
+		self.packets.push_back(retransmitted);
+		self.retransmitted_count += retransmitted as usize;
+	}
+
+	/// Get the ratio of retransmitted packets among the recorded ones, in the `[0, 1]` range.
+	///
+	/// Is `0` if no packets were recorded.
+	pub fn packet_loss(&self) -> f32 {
+		if self.packets.is_empty() {
+			0.0
+		} else {
+			self.retransmitted_count as f32 / self.packets.len() as f32
+		}
+	}
+}
+
+impl Default for PacketLoss {
+	#[inline]
+	fn default() -> Self {
+		Self::new(DEFAULT_PACKET_LOSS_WINDOW)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn packet_loss_is_computed_over_window() {
+		let mut loss = PacketLoss::new(100);
+
+		assert_eq!(loss.packet_loss(), 0.0);
+
+		// Every 4th packet is lost.
+		for i in 0 .. 1000 {
+			loss.record_sent(i % 4 == 0);
+		}
+
+		assert!((loss.packet_loss() - 0.25).abs() < 0.01);
+
+		for _ in 0 .. 50 {
+			loss.record_sent(false);
+		}
+
+		assert!((loss.packet_loss() - 0.125).abs() < 0.02);
+
+		for _ in 0 .. 100 {
+			loss.record_sent(false);
+		}
+
+		assert_eq!(loss.packet_loss(), 0.0);
+	}
+}
@@ -4,6 +4,7 @@
 //! Packets consist of 2 parts:
 //! - `Header` with technical information.
 //! - `Payload` with user data.
+//!
 //! The payload itself may consist of:
 //! - One or more instances of [`Parcel`](super::Parcel) implementations.
 //! - Part of a data stream.
@@ -16,11 +17,18 @@ use std::mem::size_of;
 use std::num::Wrapping;
 
 use super::id::ConnectionId;
+use crate::byte::{ByteSerialize, SerializationError};
 
 /// Networked data is preluded with this fixed-size user-data.
 pub type DataPrelude = [u8; 4];
 
+/// Maximum number of bytes in either the parcel or the stream segment of a single packet.
+pub const MAX_SEGMENT_BYTE_COUNT: usize = 0x7FF;
+
 /// An identifying index of the packet, used to order packets.
+///
+/// Indices wrap around, so they are ordered by the shorter distance between them, see
+/// [`wrapping_cmp()`](wrapping_cmp).
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct PacketIndex(Wrapping<u8>);
 
@@ -39,14 +47,17 @@ mod signal {
 		ConnectionAccepted,
 		/// This packet's id field is valid and should be acknowledged.
 		Synchronized,
+		/// The packet carries a fragment of a parcel too large for a single packet.
+		// (parcel bytes == fragment header + fragment data)
+		Fragment,
 	}
 
 	/// Compacted bitpatterns for signalling protocol-level information.
 	///
 	/// Consists of:
-	/// | bit(s) | 31-27      | 25           | 24                | 23               | 22                 | 21-11           | 10-0         |
-	/// |--------|------------|--------------|-------------------|------------------|--------------------|-----------------|--------------|
-	/// | value  | `[zeroes]` | synchronized | connection_accept | connection_close | connection_request | parcel(s) bytes | stream bytes |
+	/// | bit(s) | 31-27      | 26       | 25           | 24                | 23               | 22                 | 21-11           | 10-0         |
+	/// |--------|------------|----------|--------------|-------------------|------------------|--------------------|-----------------|--------------|
+	/// | value  | `[zeroes]` | fragment | synchronized | connection_accept | connection_close | connection_request | parcel(s) bytes | stream bytes |
 	#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 	pub struct SignalBits(u32);
 
@@ -54,8 +65,9 @@ mod signal {
 	const CONNECTION_CLOSE_BIT: u32 = 1 << 23;
 	const CONNECTION_ACCEPT_BIT: u32 = 1 << 24;
 	const SYNCHRONIZED_BIT: u32 = 1 << 25;
+	const FRAGMENT_BIT: u32 = 1 << 26;
 
-	const ZERO_BITS: u32 = 0xFFFF << 26;
+	const ZERO_BITS: u32 = 0xFFFF << 27;
 
 	const BYTE_COUNT_BITS: u32 = 0x7FF;
 	const FULL_BYTE_COUNT_BITS: u32 = BYTE_COUNT_BITS << 11 | BYTE_COUNT_BITS;
@@ -71,6 +83,7 @@ mod signal {
 				Signal::ConnectionClosed => self.0 |= CONNECTION_CLOSE_BIT,
 				Signal::ConnectionAccepted => self.0 |= CONNECTION_ACCEPT_BIT,
 				Signal::Synchronized => self.0 |= SYNCHRONIZED_BIT,
+				Signal::Fragment => self.0 |= FRAGMENT_BIT,
 			}
 		}
 
@@ -84,6 +97,7 @@ mod signal {
 				Signal::ConnectionClosed => self.0 &= !CONNECTION_CLOSE_BIT,
 				Signal::ConnectionAccepted => self.0 &= !CONNECTION_ACCEPT_BIT,
 				Signal::Synchronized => self.0 &= !SYNCHRONIZED_BIT,
+				Signal::Fragment => self.0 &= !FRAGMENT_BIT,
 			}
 		}
 
@@ -97,6 +111,7 @@ mod signal {
 				Signal::ConnectionClosed => (self.0 & CONNECTION_CLOSE_BIT) == CONNECTION_CLOSE_BIT,
 				Signal::ConnectionAccepted => (self.0 & CONNECTION_ACCEPT_BIT) == CONNECTION_ACCEPT_BIT,
 				Signal::Synchronized => (self.0 & SYNCHRONIZED_BIT) == SYNCHRONIZED_BIT,
+				Signal::Fragment => (self.0 & FRAGMENT_BIT) == FRAGMENT_BIT,
 			}
 		}
 
@@ -126,6 +141,18 @@ mod signal {
 			(self.0 & BYTE_COUNT_BITS) as u16
 		}
 
+		/// Construct a bitpattern from its raw representation.
+		#[inline]
+		pub(super) fn from_bits(bits: u32) -> Self {
+			Self(bits)
+		}
+
+		/// Get the raw representation of the bitpattern.
+		#[inline]
+		pub(super) fn to_bits(self) -> u32 {
+			self.0
+		}
+
 		/// Check whether the byte_count is 0 for both the stream and parcel segments.
 		#[inline]
 		pub fn is_empty(&self) -> bool {
@@ -145,21 +172,27 @@ mod signal {
 		pub fn request_connection(payload_byte_count: u16) -> Self {
 			// Since the payload length is passed from library code, this should be safe.
 			debug_assert_eq!(payload_byte_count & BYTE_COUNT_BITS as u16, payload_byte_count);
-			Self(CONNECTION_REQUEST_BIT | (payload_byte_count << 11) as u32)
+			Self(CONNECTION_REQUEST_BIT | (payload_byte_count as u32) << 11)
 		}
 
 		/// Create a bitpattern associated with a packet that is informing of the connection being rejected.
 		#[inline]
 		pub fn reject_connection(payload_byte_count: u16) -> Self {
 			debug_assert_eq!(payload_byte_count & BYTE_COUNT_BITS as u16, payload_byte_count);
-			Self(CONNECTION_CLOSE_BIT | (payload_byte_count << 11) as u32)
+			Self(CONNECTION_CLOSE_BIT | (payload_byte_count as u32) << 11)
+		}
+
+		/// Create a bitpattern associated with a packet that is informing of the connection being closed.
+		#[inline]
+		pub fn close_connection() -> Self {
+			Self(CONNECTION_CLOSE_BIT)
 		}
 
 		/// Create a bitpattern associated with a packet that is informing of the newly established connection.
 		#[inline]
 		pub fn accept_connection(payload_byte_count: u16) -> Self {
 			debug_assert_eq!(payload_byte_count & BYTE_COUNT_BITS as u16, payload_byte_count);
-			Self(CONNECTION_ACCEPT_BIT | (payload_byte_count << 11) as u32)
+			Self(CONNECTION_ACCEPT_BIT | (payload_byte_count as u32) << 11)
 		}
 
 		/// Create a bitpattern associated with an volatile (unsynchronized) packet with given parcel length.
@@ -170,6 +203,14 @@ mod signal {
 			Self((parcel_byte_count as u32) << 11)
 		}
 
+		/// Create a bitpattern associated with a volatile packet carrying a parcel fragment of given
+		/// length, including the fragment header.
+		#[inline]
+		pub fn fragment(parcel_byte_count: u16) -> Self {
+			debug_assert_eq!(parcel_byte_count & BYTE_COUNT_BITS as u16, parcel_byte_count);
+			Self(FRAGMENT_BIT | (parcel_byte_count as u32) << 11)
+		}
+
 		/// Create a bitpattern associated with a synchronized packet with given parcel and stream lengths.
 		#[inline]
 		pub fn synchronized(parcel_byte_count: u16, stream_byte_count: u16) -> Self {
@@ -184,6 +225,7 @@ mod signal {
 		pub fn is_valid_connectionless(&self) -> bool {
 			const CRITICAL_BITS: u32 =
 				ZERO_BITS
+				| FRAGMENT_BIT
 				| SYNCHRONIZED_BIT
 				| CONNECTION_ACCEPT_BIT
 				| CONNECTION_CLOSE_BIT
@@ -201,11 +243,12 @@ mod signal {
 		pub fn is_valid_connected(&self) -> bool {
 			const CRITICAL_BITS: u32 =
 				ZERO_BITS
+				| FRAGMENT_BIT
 				| SYNCHRONIZED_BIT
 				| CONNECTION_ACCEPT_BIT
 				| CONNECTION_CLOSE_BIT
 				| CONNECTION_REQUEST_BIT;
-			matches!(self.0 & CRITICAL_BITS, 0 | SYNCHRONIZED_BIT)
+			matches!(self.0 & CRITICAL_BITS, 0 | FRAGMENT_BIT | SYNCHRONIZED_BIT | CONNECTION_CLOSE_BIT)
 		}
 
 		/// Check that a given bitpattern is a valid in GNet protocol context.
@@ -213,13 +256,14 @@ mod signal {
 		pub fn is_valid(&self) -> bool {
 			const CRITICAL_BITS: u32 =
 				ZERO_BITS
+				| FRAGMENT_BIT
 				| SYNCHRONIZED_BIT
 				| CONNECTION_ACCEPT_BIT
 				| CONNECTION_CLOSE_BIT
 				| CONNECTION_REQUEST_BIT;
 			matches!(
 				self.0 & CRITICAL_BITS,
-				0 | SYNCHRONIZED_BIT | CONNECTION_ACCEPT_BIT | CONNECTION_CLOSE_BIT | CONNECTION_REQUEST_BIT,
+				0 | FRAGMENT_BIT | SYNCHRONIZED_BIT | CONNECTION_ACCEPT_BIT | CONNECTION_CLOSE_BIT | CONNECTION_REQUEST_BIT,
 			)
 		}
 	}
@@ -284,6 +328,15 @@ impl PacketHeader {
 		}
 	}
 
+	/// Create a packet header associated with a packet carrying a parcel fragment.
+	#[inline]
+	pub fn fragment(parcel_byte_count: u16) -> Self {
+		Self {
+			signal: SignalBits::fragment(parcel_byte_count),
+			.. Self::zero()
+		}
+	}
+
 	/// Create a packet header associated with a connection request.
 	#[inline]
 	pub fn request_connection(handhsake_id: DataPrelude, payload_byte_count: u16) -> Self {
@@ -304,6 +357,16 @@ impl PacketHeader {
 		}
 	}
 
+	/// Create a packet header for a packet closing provided connection.
+	#[inline]
+	pub fn close_connection(connection_id: ConnectionId) -> Self {
+		Self {
+			connection_id,
+			signal: SignalBits::close_connection(),
+			.. Self::zero()
+		}
+	}
+
 	/// Create a packet header for a connection-accepting packet.
 	#[inline]
 	pub fn accept_connection(handshake_id: DataPrelude, payload_byte_count: u16) -> Self {
@@ -330,6 +393,16 @@ impl PacketHeader {
 		}
 	}
 
+	/// Get the ids of packets within the acknowledgement window that are not acknowledged.
+	///
+	/// The window covers 64 packets preceding the `ack_packet_id`, which is itself acknowledged.
+	/// Ids are yielded from the oldest to the most recent, wrapping around correctly.
+	pub fn missing_packets(&self) -> impl Iterator<Item = PacketIndex> + '_ {
+		(1 ..= 64u8).rev()
+			.map(move |distance| PacketIndex(self.ack_packet_id.0 - Wrapping(distance)))
+			.filter(move |&packet_id| !self.acknowledges(packet_id))
+	}
+
 	/// Check that the PacketHeader is a valid connectionless GNet packet header.
 	#[inline]
 	pub fn is_valid_connectionless(&self) -> bool {
@@ -352,6 +425,37 @@ impl PacketHeader {
 		}
 	}
 
+	/// Serialize the header to its portable little-endian representation.
+	///
+	/// The representation has the same layout as the in-memory one on little-endian targets,
+	/// with the padding bytes zeroed.
+	pub fn to_le_bytes(&self) -> [u8; 24] {
+		let mut bytes = [0; 24];
+		bytes[0 .. 2].copy_from_slice(&self.connection_id.to_le_bytes());
+		bytes[2] = self.packet_id.0.0;
+		bytes[3] = self.ack_packet_id.0.0;
+		bytes[8 .. 16].copy_from_slice(&self.ack_packet_mask.to_le_bytes());
+		bytes[16 .. 20].copy_from_slice(&self.signal.to_bits().to_le_bytes());
+		bytes[20 .. 24].copy_from_slice(&self.prelude);
+		bytes
+	}
+
+	/// Deserialize a header from its portable little-endian representation.
+	///
+	/// The padding bytes are ignored.
+	pub fn from_le_bytes(bytes: [u8; 24]) -> Self {
+		let mut ack_packet_mask = [0; 8];
+		ack_packet_mask.copy_from_slice(&bytes[8 .. 16]);
+		Self {
+			connection_id: ConnectionId::from_le_bytes([bytes[0], bytes[1]]),
+			packet_id: bytes[2].into(),
+			ack_packet_id: bytes[3].into(),
+			ack_packet_mask: u64::from_le_bytes(ack_packet_mask),
+			signal: SignalBits::from_bits(u32::from_le_bytes([bytes[16], bytes[17], bytes[18], bytes[19]])),
+			prelude: [bytes[20], bytes[21], bytes[22], bytes[23]],
+		}
+	}
+
 	/// Get the total number of data payload bytes that the packet header accounts for.
 	#[inline]
 	pub fn get_payload_byte_count(&self) -> u16 {
@@ -369,11 +473,21 @@ impl PartialOrd for PacketIndex {
 impl Ord for PacketIndex {
 	#[inline]
 	fn cmp(&self, other: &Self) -> Ordering {
-		match self.0 - other.0 {
-			Wrapping(0) => Ordering::Equal,
-			x if x.0 < std::u8::MAX / 2 => Ordering::Greater,
-			_ => Ordering::Less,
-		}
+		wrapping_cmp(self.0.0, other.0.0)
+	}
+}
+
+/// Compare wrapping indices, the canonical ordering of sequential indices in GNet.
+///
+/// An index is greater than another if it is 1 to 127 steps ahead of it, and less if it is
+/// 129 to 255 steps ahead (or 1 to 127 steps behind). Indices exactly 128 steps apart are
+/// ambiguous, they are ordered by their values to keep the ordering antisymmetric.
+pub fn wrapping_cmp(lhs: u8, rhs: u8) -> Ordering {
+	match lhs.wrapping_sub(rhs) {
+		0 => Ordering::Equal,
+		1 ..= 127 => Ordering::Greater,
+		128 => lhs.cmp(&rhs),
+		_ => Ordering::Less,
 	}
 }
 
@@ -384,6 +498,13 @@ impl From<u8> for PacketIndex {
 	}
 }
 
+impl From<PacketIndex> for u8 {
+	#[inline]
+	fn from(index: PacketIndex) -> Self {
+		index.0.0
+	}
+}
+
 impl PacketIndex {
 	/// Get the next index.
 	#[inline]
@@ -398,10 +519,54 @@ impl PacketIndex {
 	}
 }
 
+impl ByteSerialize for PacketIndex {
+	#[inline]
+	fn byte_count(&self) -> usize {
+		size_of::<u8>()
+	}
+
+	#[inline]
+	fn to_bytes(&self, bytes: &mut [u8]) {
+		self.0.0.to_bytes(bytes)
+	}
+
+	#[inline]
+	fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), SerializationError> {
+		let (index, byte_count) = u8::from_bytes(bytes)?;
+		Ok((index.into(), byte_count))
+	}
+}
+
+/// Headers are serialized as their [portable representation](PacketHeader::to_le_bytes).
+///
+/// Deserializing a header that is not [valid](PacketHeader::is_valid) fails with
+/// [`UnexpectedValue`](SerializationError::UnexpectedValue).
+impl ByteSerialize for PacketHeader {
+	#[inline]
+	fn byte_count(&self) -> usize {
+		size_of::<Self>()
+	}
+
+	#[inline]
+	fn to_bytes(&self, bytes: &mut [u8]) {
+		bytes[.. size_of::<Self>()].copy_from_slice(&self.to_le_bytes());
+	}
+
+	fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), SerializationError> {
+		let (bytes, byte_count) = <[u8; 24]>::from_bytes(bytes)?;
+		let header = Self::from_le_bytes(bytes);
+		if header.is_valid() {
+			Ok((header, byte_count))
+		} else {
+			Err(SerializationError::UnexpectedValue)
+		}
+	}
+}
+
 impl PartialOrd for PacketHeader {
 	#[inline]
 	fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> {
-		self.packet_id.partial_cmp(&rhs.packet_id)
+		Some(self.cmp(rhs))
 	}
 }
 
@@ -429,7 +594,7 @@ pub fn get_data_segment(packet: &[u8]) -> &[u8] {
 /// Get the valid stream portion of the packet
 #[inline]
 pub fn get_parcel_segment(packet: &[u8]) -> &[u8] {
-	let &header = get_header(packet);
+	let header = read_header(packet);
 	let start = size_of::<PacketHeader>();
 	let end = start + header.signal.get_parcel_byte_count() as usize;
 	debug_assert!(packet.len() >= end);
@@ -439,7 +604,7 @@ pub fn get_parcel_segment(packet: &[u8]) -> &[u8] {
 /// Get the valid stream portion of the packet
 #[inline]
 pub fn get_stream_segment(packet: &[u8]) -> &[u8] {
-	let &header = get_header(packet);
+	let header = read_header(packet);
 	let start = size_of::<PacketHeader>() + header.signal.get_parcel_byte_count() as usize;
 	let end = start + header.signal.get_stream_byte_count() as usize;
 	debug_assert!(packet.len() >= end);
@@ -453,12 +618,16 @@ pub fn get_mut_data_segment(packet: &mut [u8]) -> &mut [u8] {
 	&mut packet[size_of::<PacketHeader>() ..]
 }
 
-/// Get the header segment of a packet.
+/// Read a copy of the header segment of a packet.
+///
+/// Does not require the packet to be aligned, which makes it suitable for packets buffered at
+/// arbitrary offsets (ex: by a [`Demux`](crate::endpoint::Demux)). The header is read in the
+/// little-endian byte order [`write_header()`](write_header) writes it in.
 #[inline]
-pub fn get_header(packet: &[u8]) -> &PacketHeader {
-	debug_assert!(packet.len() >= size_of::<PacketHeader>());
-	debug_assert_eq!(packet.as_ptr().align_offset(std::mem::align_of::<PacketHeader>()), 0);
-	unsafe { &*(packet.as_ptr() as *const PacketHeader) }
+pub fn read_header(packet: &[u8]) -> PacketHeader {
+	let mut bytes = [0; size_of::<PacketHeader>()];
+	bytes.copy_from_slice(&packet[.. size_of::<PacketHeader>()]);
+	PacketHeader::from_le_bytes(bytes)
 }
 
 /// Write the provided data into the provided packet data segment.
@@ -481,15 +650,13 @@ pub fn clear_remaining_data(packet: &mut [u8], offset: usize) {
 /// Write the provided packet header into provided packet.
 #[inline]
 pub fn write_header(packet: &mut [u8], header: PacketHeader) {
-	debug_assert!(packet.len() >= size_of::<PacketHeader>());
-	debug_assert_eq!(packet.as_ptr().align_offset(std::mem::align_of::<PacketHeader>()), 0);
-	unsafe { *(packet.as_mut_ptr() as *mut PacketHeader) = header }
+	packet[.. size_of::<PacketHeader>()].copy_from_slice(&header.to_le_bytes());
 }
 
 /// Read the connection id from the provided packet.
 pub fn read_connection_id(packet: &[u8]) -> ConnectionId {
 	debug_assert!(packet.len() >= size_of::<PacketHeader>());
-	get_header(packet).connection_id
+	read_header(packet).connection_id
 }
 
 /// Check whether the provided packet is a valid GNet packet.
@@ -498,7 +665,7 @@ pub fn is_valid(packet: &[u8]) -> bool {
 	if packet.len() < size_of::<PacketHeader>() {
 		return false
 	}
-	let &header = get_header(packet);
+	let header = read_header(packet);
 	header.is_valid()
 		&& header.get_payload_byte_count() <= (packet.len() - size_of::<PacketHeader>()) as u16
 }
@@ -509,7 +676,7 @@ pub fn is_valid_connected(packet: &[u8]) -> bool {
 	if packet.len() < size_of::<PacketHeader>() {
 		return false
 	}
-	let &header = get_header(packet);
+	let header = read_header(packet);
 	header.is_valid_connected()
 		&& header.get_payload_byte_count() <= (packet.len() - size_of::<PacketHeader>()) as u16
 }
@@ -520,7 +687,7 @@ pub fn is_valid_connectionless(packet: &[u8]) -> bool {
 	if packet.len() < size_of::<PacketHeader>() {
 		return false
 	}
-	let &header = get_header(packet);
+	let header = read_header(packet);
 	header.is_valid_connectionless()
 		&& header.signal.get_parcel_byte_count() <= (packet.len() - size_of::<PacketHeader>()) as u16
 }
@@ -528,6 +695,7 @@ pub fn is_valid_connectionless(packet: &[u8]) -> bool {
 #[cfg(test)]
 mod test {
 	use super::*;
+	use crate::byte::test::assert_round_trip;
 
 	#[test]
 	fn packet_index_order_is_correct() {
@@ -547,6 +715,58 @@ mod test {
 		assert!(smaller < greater);
 	}
 
+	#[test]
+	fn packet_index_order_is_antisymmetric_around_half_window() {
+		for base in [ 0, 1, 100, 127, 128, 200, 255, ] {
+			let base = PacketIndex::from(base);
+			let ahead = |distance: u8| PacketIndex(base.0 + Wrapping(distance));
+
+			assert!(ahead(126) > base && base < ahead(126));
+			assert!(ahead(127) > base && base < ahead(127));
+			assert!(ahead(129) < base && base > ahead(129));
+			assert_ne!(ahead(128).cmp(&base), Ordering::Equal);
+			assert_eq!(ahead(128).cmp(&base), base.cmp(&ahead(128)).reverse());
+		}
+
+		assert!(PacketIndex::from(128) > PacketIndex::from(0));
+		assert!(PacketIndex::from(10) < PacketIndex::from(138));
+	}
+
+	#[test]
+	fn packet_index_round_trips() {
+		assert_round_trip(&PacketIndex::from(0));
+		assert_round_trip(&PacketIndex::from(200).next());
+
+		let mut bytes = [0; 1];
+		PacketIndex::from(u8::MAX).next().to_bytes(&mut bytes);
+
+		assert_eq!(bytes, [0]);
+	}
+
+	#[test]
+	fn packet_header_serializes() {
+		let header = PacketHeader {
+			connection_id: 0x1234,
+			packet_id: 5.into(),
+			ack_packet_mask: 0xF0,
+			.. PacketHeader::volatile(17)
+		};
+		let mut bytes = [0; 24];
+
+		assert_eq!(header.byte_count(), 24);
+
+		header.to_bytes(&mut bytes);
+		let (deserialized, byte_count) = PacketHeader::from_bytes(&bytes).unwrap();
+
+		assert_eq!(byte_count, 24);
+		assert_eq!(deserialized.to_le_bytes(), header.to_le_bytes());
+		assert_eq!(PacketHeader::from_bytes(&bytes[.. 23]).unwrap_err(), SerializationError::BufferOverflow);
+
+		bytes[16 .. 20].copy_from_slice(&[0xFF; 4]);
+
+		assert_eq!(PacketHeader::from_bytes(&bytes).unwrap_err(), SerializationError::UnexpectedValue);
+	}
+
 	#[test]
 	fn packet_header_acknowledgement_is_correct() {
 		let mut header = PacketHeader::request_connection([ 1, 2, 3, 4, ], 0);
@@ -554,17 +774,85 @@ mod test {
 		header.ack_packet_id = 17.into();
 		header.ack_packet_mask = 7 << 14;
 
-		assert_eq!(header.acknowledges(17.into()), false);
+		assert!(!header.acknowledges(17.into()));
 
 		header.signal.clear_signal(Signal::ConnectionRequest);
 
-		assert_eq!(header.acknowledges(17.into()), true);
-		assert_eq!(header.acknowledges(0.into()), true);
-		assert_eq!(header.acknowledges(1.into()), true);
-		assert_eq!(header.acknowledges(2.into()), true);
+		assert!(header.acknowledges(17.into()));
+		assert!(header.acknowledges(0.into()));
+		assert!(header.acknowledges(1.into()));
+		assert!(header.acknowledges(2.into()));
+
+		assert!(!header.acknowledges(3.into()));
+		assert!(!header.acknowledges(16.into()));
+		assert!(!header.acknowledges(18.into()));
+	}
+
+	#[test]
+	fn packet_header_reports_missing_packets() {
+		let mut header = PacketHeader::volatile(0);
+		header.ack_packet_id = 2.into();
+		header.ack_packet_mask = !0;
+
+		assert_eq!(header.missing_packets().count(), 0);
+
+		// Packets 1 and 254 are missing.
+		header.ack_packet_mask &= !(1 << 0 | 1 << 3);
+
+		assert_eq!(header.missing_packets().collect::<Vec<_>>(), [254.into(), 1.into()]);
+
+		header.ack_packet_mask = 0;
+
+		assert_eq!(header.missing_packets().count(), 64);
+		assert_eq!(header.missing_packets().next(), Some(194.into()));
+		assert!(header.missing_packets().all(|packet_id| packet_id != header.ack_packet_id));
+	}
+
+	#[test]
+	fn packet_header_byte_representation_is_exact() {
+		assert_eq!(size_of::<PacketHeader>(), 24);
+
+		let header = PacketHeader {
+			connection_id: 0x1234,
+			packet_id: 0x56.into(),
+			ack_packet_id: 0x78.into(),
+			ack_packet_mask: 0x0102_0304_0506_0708,
+			signal: SignalBits::synchronized(0x123, 0x456),
+			prelude: [ 0xA1, 0xB2, 0xC3, 0xD4, ],
+		};
+		let bytes = header.to_le_bytes();
+
+		assert_eq!(bytes, [
+			0x34, 0x12, 0x56, 0x78, 0x00, 0x00, 0x00, 0x00,
+			0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01,
+			0x56, 0x1C, 0x09, 0x02, 0xA1, 0xB2, 0xC3, 0xD4,
+		]);
+
+		let deserialized = PacketHeader::from_le_bytes(bytes);
+
+		assert_eq!(deserialized.connection_id, header.connection_id);
+		assert_eq!(deserialized.packet_id, header.packet_id);
+		assert_eq!(deserialized.ack_packet_id, header.ack_packet_id);
+		assert_eq!(deserialized.ack_packet_mask, header.ack_packet_mask);
+		assert_eq!(deserialized.signal, header.signal);
+		assert_eq!(deserialized.prelude, header.prelude);
+	}
+
+	#[test]
+	fn empty_connection_request_is_valid() {
+		let mut packet = [0; size_of::<PacketHeader>()];
+		write_header(&mut packet, PacketHeader::request_connection([ 1, 2, 3, 4, ], 0));
+
+		assert!(is_valid(&packet));
+		assert!(is_valid_connectionless(&packet));
+		assert!(get_parcel_segment(&packet).is_empty());
+
+		// Truncated packets must not be mistaken for empty requests.
+		assert!(!is_valid(&packet[.. size_of::<PacketHeader>() - 1]));
+
+		write_header(&mut packet, PacketHeader::request_connection([ 1, 2, 3, 4, ], 1));
 
-		assert_eq!(header.acknowledges(3.into()), false);
-		assert_eq!(header.acknowledges(16.into()), false);
-		assert_eq!(header.acknowledges(18.into()), false);
+		assert!(!is_valid(&packet));
+		assert!(!is_valid_connectionless(&packet));
 	}
 }
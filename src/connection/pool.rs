@@ -0,0 +1,87 @@
+//! Reusable packet buffers.
+
+/// Default maximum number of buffers kept by a [`BufferPool`](BufferPool).
+pub const DEFAULT_MAX_POOLED_BUFFER_COUNT: usize = 64;
+
+/// A free-list of byte buffers.
+///
+/// Buffers returned to the pool keep their capacity, so sending packets at high rates does not
+/// allocate a new buffer for each one.
+#[derive(Debug)]
+pub struct BufferPool {
+	buffers: Vec<Vec<u8>>,
+	max_pooled_count: usize,
+	allocation_count: usize,
+}
+
+impl BufferPool {
+	/// Construct a new empty pool that keeps up to provided number of returned buffers.
+	pub fn new(max_pooled_count: usize) -> Self {
+		Self {
+			buffers: Vec::new(),
+			max_pooled_count,
+			allocation_count: 0,
+		}
+	}
+
+	/// Take an empty buffer from the pool, creating a new one if the pool has none.
+	pub fn take(&mut self) -> Vec<u8> {
+		self.buffers.pop().unwrap_or_else(|| {
+			self.allocation_count += 1;
+			Vec::new()
+		})
+	}
+
+	/// Return provided buffer to the pool for reuse.
+	///
+	/// The buffer is dropped instead if the pool is full.
+	pub fn give(&mut self, mut buffer: Vec<u8>) {
+		if self.buffers.len() < self.max_pooled_count {
+			buffer.clear();
+			self.buffers.push(buffer);
+		}
+	}
+
+	/// Get the number of buffers currently kept by the pool.
+	#[inline]
+	pub fn pooled_count(&self) -> usize {
+		self.buffers.len()
+	}
+
+	/// Get the total number of new buffers the pool had to create.
+	#[inline]
+	pub fn allocation_count(&self) -> usize {
+		self.allocation_count
+	}
+}
+
+impl Default for BufferPool {
+	#[inline]
+	fn default() -> Self {
+		Self::new(DEFAULT_MAX_POOLED_BUFFER_COUNT)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn returned_buffers_are_reused() {
+		let mut pool = BufferPool::new(1);
+
+		let mut buffer = pool.take();
+		buffer.extend_from_slice(&[1; 64]);
+		let other = pool.take();
+		pool.give(buffer);
+		pool.give(other);
+
+		assert_eq!(pool.pooled_count(), 1);
+
+		let buffer = pool.take();
+
+		assert!(buffer.is_empty());
+		assert!(buffer.capacity() >= 64);
+		assert_eq!(pool.allocation_count(), 2);
+	}
+}
@@ -0,0 +1,169 @@
+//! Round-trip time estimation of a connection.
+
+use super::packet::{wrapping_cmp, PacketIndex};
+
+use std::cmp::Ordering;
+
+use std::time::{Duration, Instant};
+
+/// Round-trip time reported by [`RttEstimator`](RttEstimator) before any samples are recorded.
+pub const DEFAULT_RTT: Duration = Duration::from_millis(100);
+
+/// Weight of a new sample in the smoothed round-trip time.
+const SMOOTHING_FACTOR: f64 = 0.125;
+
+/// State of a sent packet, that was not acknowledged yet.
+#[derive(Debug, Clone, Copy)]
+struct SentPacket {
+	time: Instant,
+	retransmitted: bool,
+}
+
+/// Estimator of the round-trip time of a connection.
+///
+/// Records the time each packet is sent at and samples the round-trip time once it is
+/// acknowledged. The estimate is an exponential moving average of the samples, which
+/// applications may use to drive client-side prediction.
+///
+/// Packets sent multiple times with the same index are not sampled, as it is ambiguous which of
+/// the sends the acknowledgement is for. Packets that are not acknowledged by the time their
+/// index is half the index space behind the newest sent one are forgotten, so that reusing the
+/// index after it wraps around is not mistaken for a retransmission.
+///
+/// **NOTE**: [`Context`](super::context::Context) does not index or acknowledge packets yet, so
+/// it does not feed an estimator. The caller must record sent and acknowledged packets itself.
+#[derive(Debug, Clone)]
+pub struct RttEstimator {
+	sent: Box<[Option<SentPacket>; 256]>,
+	newest_sent: Option<PacketIndex>,
+	smoothed: Option<Duration>,
+}
+
+impl RttEstimator {
+	/// Construct a new estimator without any samples.
+	pub fn new() -> Self {
+		Self {
+			sent: Box::new([None; 256]),
+			newest_sent: None,
+			smoothed: None,
+		}
+	}
+
+	/// Record the packet with provided index being sent at provided time.
+	///
+	/// Sending a packet with the index of one that was not acknowledged yet marks it as
+	/// retransmitted, excluding it from sampling.
+	pub fn record_sent(&mut self, index: PacketIndex, now: Instant) {
+		self.forget_stale(index);
+		let slot = &mut self.sent[u8::from(index) as usize];
+		*slot = match slot {
+			Some(packet) => Some(SentPacket { retransmitted: true, .. *packet }),
+			None => Some(SentPacket { time: now, retransmitted: false }),
+		};
+	}
+
+	/// Record the packet with provided index being acknowledged at provided time.
+	///
+	/// Acknowledgements of unknown or already acknowledged packets are ignored.
+	pub fn record_acknowledged(&mut self, index: PacketIndex, now: Instant) {
+		let packet = match self.sent[u8::from(index) as usize].take() {
+			Some(packet) if !packet.retransmitted => packet,
+			_ => return,
+		};
+		let sample = now.saturating_duration_since(packet.time);
+		self.smoothed = Some(match self.smoothed {
+			Some(smoothed) => smoothed.mul_f64(1.0 - SMOOTHING_FACTOR) + sample.mul_f64(SMOOTHING_FACTOR),
+			None => sample,
+		});
+	}
+
+	/// Forget unacknowledged packets that fall half the index space behind provided sent index.
+	fn forget_stale(&mut self, index: PacketIndex) {
+		let newest = match self.newest_sent {
+			Some(newest) if wrapping_cmp(index.into(), newest.into()) != Ordering::Greater => return,
+			Some(newest) => u8::from(newest),
+			None => u8::from(index).wrapping_sub(1),
+		};
+		self.newest_sent = Some(index);
+		let mut next = newest;
+		while next != u8::from(index) {
+			next = next.wrapping_add(1);
+			self.sent[next.wrapping_add(128) as usize] = None;
+		}
+	}
+
+	/// Get the smoothed round-trip time estimate.
+	///
+	/// Is [`DEFAULT_RTT`](DEFAULT_RTT) until the first sample is recorded.
+	#[inline]
+	pub fn rtt(&self) -> Duration {
+		self.smoothed.unwrap_or(DEFAULT_RTT)
+	}
+}
+
+impl Default for RttEstimator {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn rtt_converges() {
+		let start = Instant::now();
+		let mut estimator = RttEstimator::new();
+
+		assert_eq!(estimator.rtt(), DEFAULT_RTT);
+
+		let mut index = PacketIndex::default();
+		for i in 0 .. 100 {
+			let now = start + Duration::from_millis(10 * i);
+			estimator.record_sent(index, now);
+			estimator.record_acknowledged(index, now + Duration::from_millis(if i == 0 { 200 } else { 50 }));
+			index = index.next();
+		}
+
+		let error = estimator.rtt().as_secs_f64() - 0.05;
+
+		assert!(error.abs() < 0.001, "{:?} did not converge to 50ms", estimator.rtt());
+	}
+
+	#[test]
+	fn retransmitted_packets_are_not_sampled() {
+		let start = Instant::now();
+		let mut estimator = RttEstimator::new();
+		let index = PacketIndex::from(7);
+
+		estimator.record_sent(index, start);
+		estimator.record_sent(index, start + Duration::from_millis(300));
+		estimator.record_acknowledged(index, start + Duration::from_millis(320));
+
+		assert_eq!(estimator.rtt(), DEFAULT_RTT);
+
+		estimator.record_sent(index, start + Duration::from_millis(400));
+		estimator.record_acknowledged(index, start + Duration::from_millis(430));
+		estimator.record_acknowledged(index, start + Duration::from_millis(500));
+
+		assert_eq!(estimator.rtt(), Duration::from_millis(30));
+	}
+
+	#[test]
+	fn reused_index_is_not_a_retransmission() {
+		let start = Instant::now();
+		let mut estimator = RttEstimator::new();
+
+		// The first packet is lost, its index is reused once the indices wrap around.
+		let mut index = PacketIndex::default();
+		for i in 0 ..= 256 {
+			estimator.record_sent(index, start + Duration::from_millis(i));
+			index = index.next();
+		}
+		estimator.record_acknowledged(PacketIndex::default(), start + Duration::from_millis(276));
+
+		assert_eq!(estimator.rtt(), Duration::from_millis(20));
+	}
+}
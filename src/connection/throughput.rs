@@ -0,0 +1,133 @@
+//! Bandwidth estimation of a connection.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Default length of the window [`Throughput`](Throughput) averages over.
+pub const DEFAULT_THROUGHPUT_WINDOW: Duration = Duration::from_secs(1);
+
+/// Estimator of the bandwidth used by a connection.
+///
+/// Records the number of bytes sent and received at provided times and computes the average
+/// rate over a rolling window. Applications may use it to adapt to constrained bandwidth, for
+/// example by reducing the rate of updates.
+#[derive(Debug, Clone)]
+pub struct Throughput {
+	window: Duration,
+	sent: VecDeque<(Instant, usize)>,
+	received: VecDeque<(Instant, usize)>,
+}
+
+impl Throughput {
+	/// Construct a new estimator averaging over provided window.
+	///
+	/// # Panics
+	/// If the window is empty.
+	pub fn new(window: Duration) -> Self {
+		assert!(window > Duration::from_secs(0), "Throughput window must not be empty!");
+		Self {
+			window,
+			sent: VecDeque::new(),
+			received: VecDeque::new(),
+		}
+	}
+
+	/// Get the length of the window the estimator averages over.
+	#[inline]
+	pub fn window(&self) -> Duration {
+		self.window
+	}
+
+	/// Record provided number of bytes being sent at provided time.
+	///
+	/// Sent records older than the window are discarded.
+	#[inline]
+	pub fn record_sent(&mut self, now: Instant, byte_count: usize) {
+		discard_outdated(&mut self.sent, now, self.window);
+		self.sent.push_back((now, byte_count));
+	}
+
+	/// Record provided number of bytes being received at provided time.
+	///
+	/// Received records older than the window are discarded.
+	#[inline]
+	pub fn record_received(&mut self, now: Instant, byte_count: usize) {
+		discard_outdated(&mut self.received, now, self.window);
+		self.received.push_back((now, byte_count));
+	}
+
+	/// Get the average rate of sent and received data in bits per second over the window
+	/// preceding provided time.
+	///
+	/// Records older than the window are discarded.
+	pub fn throughput(&mut self, now: Instant) -> (f64, f64) {
+		(rate(&mut self.sent, now, self.window), rate(&mut self.received, now, self.window))
+	}
+}
+
+impl Default for Throughput {
+	#[inline]
+	fn default() -> Self {
+		Self::new(DEFAULT_THROUGHPUT_WINDOW)
+	}
+}
+
+/// Compute the rate of recorded data over the window preceding provided time, discarding
+/// outdated records.
+fn rate(records: &mut VecDeque<(Instant, usize)>, now: Instant, window: Duration) -> f64 {
+	discard_outdated(records, now, window);
+	let byte_count: usize = records.iter().map(|&(_, byte_count)| byte_count).sum();
+	(byte_count * 8) as f64 / window.as_secs_f64()
+}
+
+/// Discard records older than the window preceding provided time.
+fn discard_outdated(records: &mut VecDeque<(Instant, usize)>, now: Instant, window: Duration) {
+	while let Some(&(time, _)) = records.front() {
+		if now.saturating_duration_since(time) < window {
+			break
+		}
+		records.pop_front();
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn throughput_is_averaged_over_window() {
+		let start = Instant::now();
+		let mut throughput = Throughput::new(Duration::from_millis(500));
+
+		for i in 0 .. 10 {
+			let now = start + Duration::from_millis(100 * i);
+			throughput.record_sent(now, 1000);
+			throughput.record_received(now, 250);
+		}
+
+		// Records at 500 .. 900ms fall into the window.
+		let now = start + Duration::from_millis(950);
+		assert_eq!(throughput.throughput(now), (5.0 * 1000.0 * 8.0 * 2.0, 5.0 * 250.0 * 8.0 * 2.0));
+
+		let now = start + Duration::from_millis(1350);
+		assert_eq!(throughput.throughput(now), (1000.0 * 8.0 * 2.0, 250.0 * 8.0 * 2.0));
+
+		let now = start + Duration::from_secs(2);
+		assert_eq!(throughput.throughput(now), (0.0, 0.0));
+	}
+
+	#[test]
+	fn records_are_bounded_by_window() {
+		let start = Instant::now();
+		let mut throughput = Throughput::new(Duration::from_millis(500));
+
+		for i in 0 .. 10_000 {
+			let now = start + Duration::from_millis(i);
+			throughput.record_sent(now, 100);
+			throughput.record_received(now, 100);
+		}
+
+		assert_eq!(throughput.sent.len(), 500);
+		assert_eq!(throughput.received.len(), 500);
+	}
+}
@@ -4,16 +4,19 @@
 //! datagrams across network. More advanced ones are also responsible for demultiplexing datagrams
 //! for multiple connections, facilitating more efficient usage of network resources.
 //!
-//! The library provides basic [`Transmitter`](basic::Transmitter) and
-//! [`Demultiplexer`](basic::Demultiplexer) implementations, however the user may provide their own
+//! The library implements [`Transmit`](Transmit) for [`UdpSocket`](std::net::UdpSocket) and
+//! provides a basic [`Demultiplexer`](Demultiplexer), however the user may provide their own
 //! implementations that will be used by GNet. It is recommended to use generic [tests](test), as they
 //! test specific details that are important for correct GNet functionality.
 
 use std::io::Error as IoError;
-use std::net::{ToSocketAddrs, SocketAddr};
+use std::net::{Ipv4Addr, Ipv6Addr, ToSocketAddrs, SocketAddr, UdpSocket};
+use std::time::Duration;
 
 pub mod transmit;
 pub mod demux;
+#[cfg(all(unix, feature = "unix"))]
+pub mod config;
 
 pub use transmit::*;
 pub use demux::*;
@@ -22,18 +25,54 @@ pub use demux::*;
 pub trait Open: Sized {
 	/// Attempt to construct a new endpoint bound to provided address.
 	fn open<A: ToSocketAddrs>(addr: A) -> Result<Self, IoError>;
+
+	/// Attempt to construct a new endpoint able to reach provided remote address.
+	///
+	/// Binds to the unspecified address of the same family as the remote (`0.0.0.0` or `::`)
+	/// and a port assigned by the system, so the endpoint is not limited to the loopback interface.
+	fn open_for_remote(remote: SocketAddr) -> Result<Self, IoError> {
+		let local = match remote {
+			SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+			SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+		};
+		Self::open(local)
+	}
+}
+
+/// A trait for objects that may be constructed from an already bound socket.
+///
+/// Allows using sockets configured by the user (ex: with custom socket options or passed by the
+/// system on socket activation) instead of binding a new one with [`Open`](Open).
+pub trait FromSocket: Sized {
+	/// Attempt to construct a new endpoint using provided bound socket.
+	fn from_socket(socket: UdpSocket) -> Result<Self, IoError>;
 }
 
 impl<T: Transmit, D> Transmit for (T, D) {
-	const MAX_FRAME_LENGTH: usize = T::MAX_FRAME_LENGTH;
+	#[inline]
+	fn max_datagram_length(&self) -> usize {
+		self.0.max_datagram_length()
+	}
 	#[inline]
 	fn send_to(&self, data: &[u8], addr: SocketAddr) -> Result<usize, IoError> {
 		self.0.send_to(data, addr)
 	}
 	#[inline]
+	fn send_many(&self, datagrams: &[(&[u8], SocketAddr)]) -> Result<usize, IoError> {
+		self.0.send_many(datagrams)
+	}
+	#[inline]
 	fn try_recv_from(&self, buffer: &mut [u8]) -> Result<(usize, SocketAddr), TransmitError> {
 		self.0.try_recv_from(buffer)
 	}
+	#[inline]
+	fn recv_from_timeout(&self, buffer: &mut [u8], timeout: Option<Duration>) -> Result<(usize, SocketAddr), TransmitError> {
+		self.0.recv_from_timeout(buffer, timeout)
+	}
+	#[inline]
+	fn stats(&self) -> EndpointStats {
+		self.0.stats()
+	}
 }
 
 impl<T, K, D: Demux<K>> Demux<K> for (T, D) {
@@ -59,13 +98,30 @@ impl<T, K, D: Demux<K>> Demux<K> for (T, D) {
 		self.1.process(key, functor);
 	}
 	#[inline]
+	fn process_all<F: FnMut(K, (&[u8], SocketAddr))>(&mut self, functor: F) where K: Copy {
+		self.1.process_all(functor)
+	}
+	#[inline]
 	fn get_buffered_counts(&self, key: K) -> (usize, usize) {
 		self.1.get_buffered_counts(key)
 	}
 }
 
+impl<T, K: Ord, D: OrderedDemux<K>> OrderedDemux<K> for (T, D) {
+	#[inline]
+	fn process_ordered<F: FnMut(K, (&[u8], SocketAddr))>(&mut self, functor: F) {
+		self.1.process_ordered(functor)
+	}
+}
+
 impl<T: Open, D: Default> Open for (T, D) {
 	fn open<A: ToSocketAddrs>(addr: A) -> Result<Self, IoError> {
 		Ok((T::open(addr)?, D::default()))
 	}
 }
+
+impl<T: FromSocket, D: Default> FromSocket for (T, D) {
+	fn from_socket(socket: UdpSocket) -> Result<Self, IoError> {
+		Ok((T::from_socket(socket)?, D::default()))
+	}
+}
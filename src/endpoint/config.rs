@@ -0,0 +1,209 @@
+//! Configuration of socket options that are not exposed by the standard library.
+//!
+//! Requires the `unix` feature.
+
+use crate::endpoint::FromSocket;
+
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use std::mem::{size_of, zeroed};
+use std::net::{ToSocketAddrs, SocketAddr, SocketAddrV6, UdpSocket};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+
+/// Traffic class requesting expedited forwarding (DSCP 46), suited for latency-sensitive traffic.
+pub const EXPEDITED_FORWARDING: u8 = 46 << 2;
+
+/// Options applied to a socket when it is opened.
+///
+/// The flow label of IPv6 packets is left to the operating system, as setting it explicitly
+/// requires leasing the label from the kernel.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SocketConfig {
+	/// Traffic class (DSCP and ECN bits) of sent datagrams, `None` keeps the system default.
+	///
+	/// Sets `IPV6_TCLASS` for IPv6 sockets and `IP_TOS` for IPv4 ones. Routers are free to ignore it.
+	pub traffic_class: Option<u8>,
+	/// Whether IPv6 sockets also exchange IPv4 datagrams, `None` keeps the system default.
+	///
+	/// Sets `IPV6_V6ONLY` before binding, allowing a single socket bound to `::` to serve both
+	/// families. Ignored for IPv4 addresses.
+	pub dual_stack: Option<bool>,
+}
+
+impl SocketConfig {
+	/// Attempt to construct a new endpoint bound to provided address and configured with
+	/// this configuration.
+	pub fn open<T: FromSocket, A: ToSocketAddrs>(&self, addr: A) -> Result<T, IoError> {
+		let socket = match self.dual_stack {
+			Some(dual_stack) => bind_any(addr, dual_stack)?,
+			None => UdpSocket::bind(addr)?,
+		};
+		self.apply(&socket)?;
+		T::from_socket(socket)
+	}
+
+	/// Apply the configuration to provided bound socket.
+	pub fn apply(&self, socket: &UdpSocket) -> Result<(), IoError> {
+		if let Some(traffic_class) = self.traffic_class {
+			let (level, name) = traffic_class_option(socket)?;
+			set_option(socket, level, name, traffic_class as libc::c_int)?;
+		}
+		Ok(())
+	}
+}
+
+/// Get the traffic class of datagrams sent through provided socket.
+pub fn get_traffic_class(socket: &UdpSocket) -> Result<u8, IoError> {
+	let (level, name) = traffic_class_option(socket)?;
+	Ok(get_option(socket, level, name)? as u8)
+}
+
+/// Get whether provided socket exchanges both IPv6 and IPv4 datagrams.
+///
+/// IPv4 sockets are never dual-stack.
+pub fn is_dual_stack(socket: &UdpSocket) -> Result<bool, IoError> {
+	if socket.local_addr()?.is_ipv6() {
+		Ok(get_option(socket, libc::IPPROTO_IPV6, libc::IPV6_V6ONLY)? == 0)
+	} else {
+		Ok(false)
+	}
+}
+
+/// Bind a socket to the first of provided addresses that succeeds, configuring IPv6 ones to be
+/// dual-stack or not before binding.
+fn bind_any<A: ToSocketAddrs>(addr: A, dual_stack: bool) -> Result<UdpSocket, IoError> {
+	let mut last_error = None;
+	for addr in addr.to_socket_addrs()? {
+		let result = match addr {
+			SocketAddr::V4(_) => UdpSocket::bind(addr),
+			SocketAddr::V6(addr) => bind_v6(addr, dual_stack),
+		};
+		match result {
+			Ok(socket) => return Ok(socket),
+			Err(error) => last_error = Some(error),
+		}
+	}
+	Err(last_error.unwrap_or_else(|| IoError::new(IoErrorKind::InvalidInput, "could not resolve to any addresses")))
+}
+
+fn bind_v6(addr: SocketAddrV6, dual_stack: bool) -> Result<UdpSocket, IoError> {
+	// SAFETY: creating a socket has no preconditions.
+	let fd = unsafe { libc::socket(libc::AF_INET6, libc::SOCK_DGRAM, 0) };
+	if fd < 0 {
+		return Err(IoError::last_os_error())
+	}
+	// SAFETY: the descriptor was just created and is not owned by anything else.
+	let socket = unsafe { UdpSocket::from_raw_fd(fd) };
+	set_option(&socket, libc::IPPROTO_IPV6, libc::IPV6_V6ONLY, !dual_stack as libc::c_int)?;
+
+	// SAFETY: sockaddr_in6 is a plain C struct, for which zero is a valid value.
+	let mut sockaddr: libc::sockaddr_in6 = unsafe { zeroed() };
+	#[cfg(any(
+		target_os = "macos",
+		target_os = "ios",
+		target_os = "freebsd",
+		target_os = "openbsd",
+		target_os = "netbsd",
+		target_os = "dragonfly",
+	))]
+	{
+		sockaddr.sin6_len = size_of::<libc::sockaddr_in6>() as u8;
+	}
+	sockaddr.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+	sockaddr.sin6_port = addr.port().to_be();
+	sockaddr.sin6_flowinfo = addr.flowinfo();
+	sockaddr.sin6_addr.s6_addr = addr.ip().octets();
+	sockaddr.sin6_scope_id = addr.scope_id();
+	// SAFETY: the address and its length describe a valid sockaddr_in6.
+	let result = unsafe {
+		libc::bind(
+			fd,
+			&sockaddr as *const _ as *const libc::sockaddr,
+			size_of::<libc::sockaddr_in6>() as libc::socklen_t,
+		)
+	};
+	if result != 0 {
+		return Err(IoError::last_os_error())
+	}
+	Ok(socket)
+}
+
+/// Get the level and name of the traffic class option of provided socket.
+fn traffic_class_option(socket: &UdpSocket) -> Result<(libc::c_int, libc::c_int), IoError> {
+	if socket.local_addr()?.is_ipv6() {
+		Ok((libc::IPPROTO_IPV6, libc::IPV6_TCLASS))
+	} else {
+		Ok((libc::IPPROTO_IP, libc::IP_TOS))
+	}
+}
+
+fn get_option(socket: &UdpSocket, level: libc::c_int, name: libc::c_int) -> Result<libc::c_int, IoError> {
+	let mut value: libc::c_int = 0;
+	let mut len = size_of::<libc::c_int>() as libc::socklen_t;
+	// SAFETY: the value and its length describe a valid c_int.
+	let result = unsafe {
+		libc::getsockopt(socket.as_raw_fd(), level, name, &mut value as *mut _ as *mut libc::c_void, &mut len)
+	};
+	if result != 0 {
+		return Err(IoError::last_os_error())
+	}
+	Ok(value)
+}
+
+fn set_option(socket: &UdpSocket, level: libc::c_int, name: libc::c_int, value: libc::c_int) -> Result<(), IoError> {
+	// SAFETY: the value and its length describe a valid c_int.
+	let result = unsafe {
+		libc::setsockopt(
+			socket.as_raw_fd(),
+			level,
+			name,
+			&value as *const _ as *const libc::c_void,
+			size_of::<libc::c_int>() as libc::socklen_t,
+		)
+	};
+	if result != 0 {
+		return Err(IoError::last_os_error())
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	use std::net::Ipv6Addr;
+
+	#[test]
+	fn traffic_class_is_set_on_open() {
+		let config = SocketConfig { traffic_class: Some(EXPEDITED_FORWARDING), ..Default::default() };
+
+		let socket: UdpSocket = config.open(SocketAddr::from(([ 0, 0, 0, 0, 0, 0, 0, 1, ], 10011))).unwrap();
+		assert_eq!(get_traffic_class(&socket).unwrap(), EXPEDITED_FORWARDING);
+
+		let socket: UdpSocket = config.open(SocketAddr::from(([ 127, 0, 0, 1, ], 10012))).unwrap();
+		assert_eq!(get_traffic_class(&socket).unwrap(), EXPEDITED_FORWARDING);
+
+		let socket: UdpSocket = SocketConfig::default().open(SocketAddr::from(([ 127, 0, 0, 1, ], 10013))).unwrap();
+		assert_eq!(get_traffic_class(&socket).unwrap(), 0);
+	}
+
+	#[test]
+	fn dual_stack_socket_receives_ipv4() {
+		let config = SocketConfig { dual_stack: Some(true), ..Default::default() };
+		let socket: UdpSocket = config.open(SocketAddr::from((Ipv6Addr::UNSPECIFIED, 10022))).unwrap();
+
+		assert!(is_dual_stack(&socket).unwrap());
+
+		let sender = UdpSocket::bind(SocketAddr::from(([ 127, 0, 0, 1, ], 10023))).unwrap();
+		sender.send_to(b"IPV4", SocketAddr::from(([ 127, 0, 0, 1, ], 10022))).unwrap();
+		let mut buffer = [0; 16];
+		let (len, src) = socket.recv_from(&mut buffer).unwrap();
+
+		assert_eq!(&buffer[.. len], b"IPV4");
+		assert_eq!(src.port(), 10023);
+
+		let config = SocketConfig { dual_stack: Some(false), ..Default::default() };
+		let socket: UdpSocket = config.open(SocketAddr::from((Ipv6Addr::UNSPECIFIED, 10024))).unwrap();
+
+		assert!(!is_dual_stack(&socket).unwrap());
+	}
+}
@@ -1,7 +1,10 @@
 //! [`Demux`](Demux) trait definition, implementation and test.
 
+mod address;
 #[cfg(test)]
-pub mod test;
+mod test;
+
+pub use address::{AddressBuffer, DEFAULT_MAX_ADDRESS_COUNT, DEFAULT_MAX_DATAGRAMS_PER_ADDRESS};
 
 use std::collections::HashMap;
 use std::hash::Hash;
@@ -27,7 +30,7 @@ pub trait Demux<K> {
 	///
 	/// # Notes
 	/// - The length and source address of the datagram should be recorded as it needs
-	/// to be returned with [`pop`](Mux::pop).
+	///   to be passed to the functor in [`process`](Demux::process).
 	/// - The connection may be assumed to be allowed at the time of invocation.
 	/// - The implementation may assume the key is allowed at the time of invocation.
 	fn push(&mut self, key: K, dgram: (&[u8], SocketAddr));
@@ -40,6 +43,15 @@ pub trait Demux<K> {
 	/// - The implementation may assume the key is allowed at the time of invocation.
 	fn process<F: FnMut((&[u8], SocketAddr))>(&mut self, key: K, functor: F);
 
+	/// Process buffered datagrams of all allowed keys by invoking the provided functor with the
+	/// key and each datagram.
+	///
+	/// # Notes
+	/// - The functor should be invoked exactly once for each buffered datagram.
+	/// - The order of invocations is up to the implementation, see
+	///   [`OrderedDemux`](OrderedDemux) for a deterministic one.
+	fn process_all<F: FnMut(K, (&[u8], SocketAddr))>(&mut self, functor: F) where K: Copy;
+
 	/// Get the number of buffered datagrams and the number of buffered bytes for those datagrams.
 	///
 	/// # Notes
@@ -47,10 +59,30 @@ pub trait Demux<K> {
 	fn get_buffered_counts(&self, key: K) -> (usize, usize);
 }
 
+/// A trait for demultiplexers able to process buffered datagrams of all keys in a deterministic
+/// order.
+///
+/// Useful for reproducible tests and replays, where the order of processing across connections
+/// must not change between runs.
+pub trait OrderedDemux<K: Ord>: Demux<K> {
+	/// Process buffered datagrams of all allowed keys by invoking the provided functor.
+	///
+	/// # Notes
+	/// - The keys must be visited in ascending order.
+	/// - Datagrams of the same key must be visited in the order they were pushed.
+	/// - The functor should be invoked exactly once for each buffered datagram.
+	fn process_ordered<F: FnMut(K, (&[u8], SocketAddr))>(&mut self, functor: F);
+}
+
+/// Default [`Demux`](Demux) implementation.
+///
+/// Buffers datagrams of each allowed key in a contiguous byte vector.
+pub type Demultiplexer<K> = HashMap<K, (Vec<u8>, Vec<(usize, SocketAddr)>)>;
+
 impl<K: Hash + Eq> Demux<K> for HashMap<K, (Vec<u8>, Vec<(usize, SocketAddr)>)> {
 	#[inline]
 	fn allow(&mut self, key: K) {
-		self.entry(key).or_insert_with(Default::default);
+		self.entry(key).or_default();
 	}
 	#[inline]
 	fn block(&mut self, key: K) {
@@ -76,8 +108,29 @@ impl<K: Hash + Eq> Demux<K> for HashMap<K, (Vec<u8>, Vec<(usize, SocketAddr)>)>
 		infos.clear();
 		bytes.clear();
 	}
+	fn process_all<F: FnMut(K, (&[u8], SocketAddr))>(&mut self, mut functor: F) where K: Copy {
+		for (&key, (bytes, infos)) in self.iter_mut() {
+			let mut offset = 0;
+			for (len, src) in infos.iter() {
+				functor(key, (&bytes[offset .. offset + *len], *src));
+				offset += *len;
+			}
+			infos.clear();
+			bytes.clear();
+		}
+	}
 	fn get_buffered_counts(&self, key: K) -> (usize, usize) {
 		let (bytes, infos) = self.get(&key).unwrap();
 		(infos.len(), bytes.len())
 	}
 }
+
+impl<K: Hash + Ord + Copy> OrderedDemux<K> for HashMap<K, (Vec<u8>, Vec<(usize, SocketAddr)>)> {
+	fn process_ordered<F: FnMut(K, (&[u8], SocketAddr))>(&mut self, mut functor: F) {
+		let mut keys: Vec<K> = self.keys().copied().collect();
+		keys.sort_unstable();
+		for key in keys {
+			self.process(key, |dgram| functor(key, dgram));
+		}
+	}
+}
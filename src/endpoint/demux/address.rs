@@ -0,0 +1,162 @@
+//! Buffering of datagrams keyed by their source address.
+
+use super::Demux;
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+/// Default maximum number of datagrams buffered for a single address.
+pub const DEFAULT_MAX_DATAGRAMS_PER_ADDRESS: usize = 8;
+
+/// Default maximum number of addresses datagrams are buffered for at once.
+///
+/// See [`AddressBuffer::set_max_address_count()`](AddressBuffer::set_max_address_count).
+pub const DEFAULT_MAX_ADDRESS_COUNT: usize = 1024;
+
+/// Buffer of datagrams keyed by their source address.
+///
+/// Used for datagrams that arrive before their connection has an id, such as early handshake
+/// packets. Datagrams from the same address are kept in arrival order and may be
+/// [migrated](AddressBuffer::migrate) to a [`Demux`](Demux) key once one is assigned.
+#[derive(Debug)]
+pub struct AddressBuffer {
+	buffers: HashMap<SocketAddr, (Vec<u8>, Vec<usize>)>,
+	max_datagrams_per_address: usize,
+	max_address_count: usize,
+}
+
+impl AddressBuffer {
+	/// Construct a new buffer that keeps up to provided number of datagrams for each address.
+	pub fn new(max_datagrams_per_address: usize) -> Self {
+		Self {
+			buffers: HashMap::new(),
+			max_datagrams_per_address,
+			max_address_count: DEFAULT_MAX_ADDRESS_COUNT,
+		}
+	}
+
+	/// Set the maximum number of addresses datagrams are buffered for at once.
+	///
+	/// Bounds the memory used when datagrams arrive from many (possibly spoofed) addresses.
+	/// Defaults to [`DEFAULT_MAX_ADDRESS_COUNT`](DEFAULT_MAX_ADDRESS_COUNT).
+	#[inline]
+	pub fn set_max_address_count(&mut self, count: usize) {
+		self.max_address_count = count;
+	}
+
+	/// Buffer provided datagram.
+	///
+	/// Returns `false` if the datagram was dropped, because too many datagrams from the same
+	/// address are already buffered, or the address is new and datagrams of too many addresses
+	/// are already buffered.
+	pub fn push(&mut self, (dgram, src): (&[u8], SocketAddr)) -> bool {
+		match self.buffers.get_mut(&src) {
+			Some((bytes, lens)) => {
+				if lens.len() >= self.max_datagrams_per_address {
+					return false
+				}
+				bytes.extend_from_slice(dgram);
+				lens.push(dgram.len());
+			},
+			None => {
+				if self.max_datagrams_per_address == 0 || self.buffers.len() >= self.max_address_count {
+					return false
+				}
+				self.buffers.insert(src, (dgram.to_vec(), vec![dgram.len()]));
+			},
+		}
+		true
+	}
+
+	/// Get the number of datagrams and bytes buffered for provided address.
+	pub fn get_buffered_counts(&self, addr: SocketAddr) -> (usize, usize) {
+		self.buffers.get(&addr).map_or((0, 0), |(bytes, lens)| (lens.len(), bytes.len()))
+	}
+
+	/// Process datagrams buffered for provided address in arrival order by invoking the provided
+	/// functor, unbuffering them.
+	pub fn process<F: FnMut(&[u8])>(&mut self, addr: SocketAddr, mut functor: F) {
+		if let Some((bytes, lens)) = self.buffers.remove(&addr) {
+			let mut offset = 0;
+			for len in lens {
+				functor(&bytes[offset .. offset + len]);
+				offset += len;
+			}
+		}
+	}
+
+	/// Move datagrams buffered for provided address to provided demultiplexer key, preserving
+	/// arrival order.
+	///
+	/// The key must be allowed by the demultiplexer.
+	pub fn migrate<K: Copy, D: Demux<K>>(&mut self, addr: SocketAddr, demux: &mut D, key: K) {
+		self.process(addr, |dgram| demux.push(key, (dgram, addr)));
+	}
+
+	/// Drop all datagrams buffered for provided address.
+	#[inline]
+	pub fn discard(&mut self, addr: SocketAddr) {
+		self.buffers.remove(&addr);
+	}
+}
+
+impl Default for AddressBuffer {
+	#[inline]
+	fn default() -> Self {
+		Self::new(DEFAULT_MAX_DATAGRAMS_PER_ADDRESS)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::endpoint::Demultiplexer;
+
+	#[test]
+	fn buffered_datagrams_migrate_to_key() {
+		let client = SocketAddr::from(([ 127, 0, 0, 1, ], 1));
+		let other = SocketAddr::from(([ 127, 0, 0, 1, ], 2));
+		let mut buffer = AddressBuffer::new(2);
+
+		assert!(buffer.push((b"FIRST", client)));
+		assert!(buffer.push((b"OTHER", other)));
+		assert!(buffer.push((b"SECOND", client)));
+		assert!(!buffer.push((b"THIRD", client)));
+		assert_eq!(buffer.get_buffered_counts(client), (2, 11));
+
+		let mut demux = Demultiplexer::<u16>::new();
+		demux.allow(5);
+		buffer.migrate(client, &mut demux, 5);
+
+		let mut processed = Vec::new();
+		demux.process(5, |(dgram, src)| {
+			assert_eq!(src, client);
+			processed.push(dgram.to_vec());
+		});
+
+		assert_eq!(processed, [b"FIRST".to_vec(), b"SECOND".to_vec()]);
+		assert_eq!(buffer.get_buffered_counts(client), (0, 0));
+		assert_eq!(buffer.get_buffered_counts(other), (1, 5));
+	}
+
+	#[test]
+	fn address_count_is_bounded() {
+		let mut buffer = AddressBuffer::default();
+		buffer.set_max_address_count(2);
+
+		assert!(buffer.push((b"FIRST", SocketAddr::from(([ 127, 0, 0, 1, ], 1)))));
+		assert!(buffer.push((b"SECOND", SocketAddr::from(([ 127, 0, 0, 1, ], 2)))));
+		assert!(!buffer.push((b"THIRD", SocketAddr::from(([ 127, 0, 0, 1, ], 3)))));
+		assert!(buffer.push((b"FOURTH", SocketAddr::from(([ 127, 0, 0, 1, ], 1)))));
+		assert_eq!(buffer.buffers.len(), 2);
+
+		buffer.discard(SocketAddr::from(([ 127, 0, 0, 1, ], 2)));
+
+		assert!(buffer.push((b"FIFTH", SocketAddr::from(([ 127, 0, 0, 1, ], 3)))));
+
+		let mut buffer = AddressBuffer::new(0);
+
+		assert!(!buffer.push((b"DROPPED", SocketAddr::from(([ 127, 0, 0, 1, ], 1)))));
+		assert!(buffer.buffers.is_empty());
+	}
+}
@@ -1,6 +1,6 @@
 //! Generic functions for testing [`Demux`](Demux) implementations.
 
-use super::Demux;
+use super::{Demux, OrderedDemux};
 
 use std::collections::HashMap;
 use std::net::SocketAddr;
@@ -27,8 +27,12 @@ pub fn generic_demux_test<D: Demux<u32>>(demultiplexer: &mut D) {
 	demultiplexer.push(0, datagrams[0]);
 	demultiplexer.push(1, datagrams[1]);
 	demultiplexer.push(1, datagrams[2]);
+	assert_eq!(demultiplexer.get_buffered_counts(0), (1, 1));
+	assert_eq!(demultiplexer.get_buffered_counts(1), (2, 6));
+
 	demultiplexer.block(0);
 	demultiplexer.allow(0);
+	assert_eq!(demultiplexer.get_buffered_counts(0), (0, 0));
 
 	demultiplexer.process(0, |_| panic!("Blocked datagrams were not dropped!"));
 
@@ -44,8 +48,58 @@ pub fn generic_demux_test<D: Demux<u32>>(demultiplexer: &mut D) {
 	assert!(!found_dgrams[0], "Processed datagram not associated with required key!");
 	assert!(found_dgrams[1], "Did not process a buffered datagram!");
 	assert!(found_dgrams[2], "Did not process a buffered datagram!");
+	assert_eq!(demultiplexer.get_buffered_counts(1), (0, 0));
 
 	demultiplexer.process(1, |_| panic!("Did not unbuffered processed datagrams!"));
+
+	for (key, &dgram) in [0, 1, 1].iter().zip(datagrams.iter()) {
+		demultiplexer.push(*key, dgram);
+	}
+	let mut found_dgrams = Vec::new();
+	demultiplexer.process_all(|key, dgram| {
+		let index = datagrams
+			.iter()
+			.position(|item| *item == dgram)
+			.expect("Failed to find a sent datagram!");
+		found_dgrams.push((key, index));
+	});
+	found_dgrams.sort_unstable();
+	assert_eq!(found_dgrams, [(0, 0), (1, 1), (1, 2)]);
+
+	demultiplexer.process_all(|_, _| panic!("Did not unbuffered processed datagrams!"));
+}
+
+/// Test that provided [`OrderedDemux`](OrderedDemux) implementation visits datagrams in order.
+pub fn generic_ordered_demux_test<D: OrderedDemux<u32>>(demultiplexer: &mut D) {
+	let src = SocketAddr::from(([ 127, 0, 0, 1, ], 0));
+	let keys = [7, 3, 42, 0, 15, 1];
+
+	for &key in keys.iter() {
+		demultiplexer.allow(key);
+	}
+	for round in 0 .. 3u8 {
+		for &key in keys.iter().rev() {
+			demultiplexer.push(key, (&[key as u8, round], src));
+		}
+	}
+
+	let mut visited = Vec::new();
+	demultiplexer.process_ordered(|key, (dgram, _)| {
+		assert_eq!(dgram[0], key as u8, "Processed datagram not associated with visited key!");
+		visited.push((key, dgram[1]));
+	});
+
+	let mut expected = Vec::new();
+	let mut sorted_keys = keys;
+	sorted_keys.sort_unstable();
+	for &key in sorted_keys.iter() {
+		for round in 0 .. 3 {
+			expected.push((key, round));
+		}
+	}
+	assert_eq!(visited, expected);
+
+	demultiplexer.process_ordered(|_, _| panic!("Did not unbuffered processed datagrams!"));
 }
 
 #[test]
@@ -53,3 +107,12 @@ fn hash_map_demultiplexes() {
 	let mut hash_map = HashMap::new();
 	generic_demux_test(&mut hash_map);
 }
+
+#[test]
+fn hash_map_processes_in_order() {
+	// Separately constructed maps use different hashing seeds, exercising different iteration orders.
+	for _ in 0 .. 8 {
+		let mut hash_map = HashMap::new();
+		generic_ordered_demux_test(&mut hash_map);
+	}
+}
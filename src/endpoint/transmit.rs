@@ -1,11 +1,28 @@
 //! [`Transmit`](Transmit) trait definition, implementation and unit test.
 
+#[cfg(feature = "tokio")]
+mod asynchronous;
 mod basic;
+#[cfg(all(target_os = "linux", feature = "unix"))]
+mod mmsg;
+mod multi;
+mod sized;
 #[cfg(test)]
-pub mod test;
+mod test;
+
+#[cfg(feature = "tokio")]
+pub use asynchronous::AsyncTransmit;
+pub use multi::{MultiTransmit, Routing};
+pub use sized::{SizedSocket, MAX_UDP_DATAGRAM_LENGTH};
 
 use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 use std::net::SocketAddr;
+use std::ops::AddAssign;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Conservative approximation of the MTU, used as the maximum datagram length by default.
+pub const DEFAULT_MAX_DATAGRAM_LENGTH: usize = 1200;
 
 /// An error associated with an endpoint.
 #[derive(Debug)]
@@ -18,6 +35,19 @@ pub enum TransmitError {
 	Io(IoError),
 }
 
+/// Cumulative counts of datagrams that passed through an endpoint.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EndpointStats {
+	/// Number of bytes in successfully sent datagrams.
+	pub bytes_sent: u64,
+	/// Number of successfully sent datagrams.
+	pub datagrams_sent: u64,
+	/// Number of received datagrams, including dropped ones.
+	pub datagrams_received: u64,
+	/// Number of received datagrams that were dropped for having a malformed header.
+	pub datagrams_dropped: u64,
+}
+
 /// A trait for objects that transmit data frames across network.
 ///
 /// Implementors of `Transmit` trait are called 'transmitters'.
@@ -36,6 +66,8 @@ pub enum TransmitError {
 /// - Packets are delivered in no particular order.
 pub trait Transmit {
 	/// Current maximum size of a sent data packet.
+	///
+	/// Buffers for sent and received datagrams are sized according to this value.
 	fn max_datagram_length(&self) -> usize;
 
 	/// Send provided data to the provided address.
@@ -44,16 +76,98 @@ pub trait Transmit {
 	/// responsible for the failure.
 	///
 	/// # Note
-	/// Implementation may assume data is at most [`MAX_FRAME_LENGTH`](MAX_FRAME_LENGTH) bytes.
+	/// Implementation may assume data is at most [`max_datagram_length()`](Self::max_datagram_length) bytes.
 	fn send_to(&self, data: &[u8], addr: SocketAddr) -> Result<usize, IoError>;
 
+	/// Send provided datagrams to their addresses.
+	///
+	/// Return the number of datagrams sent. Sending stops at the first failed datagram, the error
+	/// is returned only if no datagrams were sent.
+	///
+	/// Allows flushing a connection with fewer system calls, the default implementation invokes
+	/// [`send_to()`](Self::send_to) for each datagram.
+	fn send_many(&self, datagrams: &[(&[u8], SocketAddr)]) -> Result<usize, IoError> {
+		for (sent, &(data, addr)) in datagrams.iter().enumerate() {
+			if let Err(error) = self.send_to(data, addr) {
+				return if sent == 0 { Err(error) } else { Ok(sent) }
+			}
+		}
+		Ok(datagrams.len())
+	}
+
 	/// Attempt to recover an incoming datagram.
 	///
 	/// Return the number of bytes written to the buffer and the origin of the datagram on success.
 	///
 	/// # Note
-	/// - May assume the buffer is able to hold [`MAX_FRAME_LENGTH`](MAX_FRAME_LENGTH) bytes.
+	/// - May assume the buffer is able to hold [`max_datagram_length()`](Self::max_datagram_length) bytes.
 	fn try_recv_from(&self, buffer: &mut [u8]) -> Result<(usize, SocketAddr), TransmitError>;
+
+	/// Wait for an incoming datagram for up to provided duration, or indefinitely if it is `None`.
+	///
+	/// Return the number of bytes written to the buffer and the origin of the datagram on success,
+	/// or [`NoPendingPackets`](TransmitError::NoPendingPackets) if none arrived in time.
+	///
+	/// Allows threads with nothing else to do to wait for datagrams without busy-looping. The
+	/// default implementation polls [`try_recv_from()`](Self::try_recv_from) every millisecond.
+	///
+	/// # Note
+	/// - May assume the buffer is able to hold [`max_datagram_length()`](Self::max_datagram_length) bytes.
+	fn recv_from_timeout(&self, buffer: &mut [u8], timeout: Option<Duration>) -> Result<(usize, SocketAddr), TransmitError> {
+		let deadline = timeout.map(|timeout| Instant::now() + timeout);
+		loop {
+			match self.try_recv_from(buffer) {
+				Err(TransmitError::NoPendingPackets) => {
+					if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+						return Err(TransmitError::NoPendingPackets)
+					}
+					sleep(Duration::from_millis(1));
+				},
+				result => return result,
+			}
+		}
+	}
+
+	/// Get the statistics of datagrams transmitted so far.
+	///
+	/// Transmitters are not required to keep statistics, the default implementation returns zeros.
+	#[inline]
+	fn stats(&self) -> EndpointStats {
+		EndpointStats::default()
+	}
+}
+
+/// Transmitters disagree on the maximum length of exchanged datagrams.
+///
+/// Datagrams built for the longer length may not be received by the other transmitter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DatagramLengthMismatch {
+	/// Maximum datagram length of the first transmitter.
+	pub first: usize,
+	/// Maximum datagram length of the second transmitter.
+	pub second: usize,
+}
+
+/// Check that provided transmitters agree on the maximum length of exchanged datagrams.
+///
+/// Intended to be used before establishing a connection between transmitters, as mismatched
+/// lengths produce packets that can not be decoded by the other end.
+pub fn validate_compatibility<A: Transmit, B: Transmit>(first: &A, second: &B) -> Result<(), DatagramLengthMismatch> {
+	let (first, second) = (first.max_datagram_length(), second.max_datagram_length());
+	if first == second {
+		Ok(())
+	} else {
+		Err(DatagramLengthMismatch { first, second })
+	}
+}
+
+impl AddAssign for EndpointStats {
+	fn add_assign(&mut self, rhs: Self) {
+		self.bytes_sent += rhs.bytes_sent;
+		self.datagrams_sent += rhs.datagrams_sent;
+		self.datagrams_received += rhs.datagrams_received;
+		self.datagrams_dropped += rhs.datagrams_dropped;
+	}
 }
 
 impl From<IoError> for TransmitError {
@@ -97,6 +211,14 @@ impl std::fmt::Display for TransmitError {
 	}
 }
 
+impl std::fmt::Display for DatagramLengthMismatch {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "maximum datagram lengths differ: {} and {} bytes", self.first, self.second)
+	}
+}
+
+impl std::error::Error for DatagramLengthMismatch {}
+
 impl std::error::Error for TransmitError {
 	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
 		match self {
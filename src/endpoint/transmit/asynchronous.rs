@@ -0,0 +1,104 @@
+//! Asynchronous transmitter trait and its implementation for [tokio](tokio) sockets.
+
+use crate::endpoint::{FromSocket, Open};
+
+use super::{Transmit, TransmitError, DEFAULT_MAX_DATAGRAM_LENGTH};
+
+use std::future::Future;
+use std::io::Error as IoError;
+use std::net::{ToSocketAddrs, SocketAddr};
+
+use tokio::net::UdpSocket;
+
+/// A [`Transmit`](Transmit) counterpart for objects that transmit data frames asynchronously.
+///
+/// Lets applications built on an async runtime await datagrams instead of polling for them.
+/// The same requirements as for [`Transmit`](Transmit) implementors apply.
+pub trait AsyncTransmit {
+	/// Current maximum size of a sent data packet.
+	///
+	/// Buffers for sent and received datagrams are sized according to this value.
+	fn max_datagram_length(&self) -> usize;
+
+	/// Send provided data to the provided address, waiting for the transmitter to be writable.
+	///
+	/// Return the number of bytes sent, which must be at least the length of `data`. Or the error
+	/// responsible for the failure.
+	///
+	/// # Note
+	/// Implementation may assume data is at most [`max_datagram_length()`](Self::max_datagram_length) bytes.
+	fn send_to(&self, data: &[u8], addr: SocketAddr) -> impl Future<Output = Result<usize, IoError>> + Send;
+
+	/// Wait for an incoming datagram.
+	///
+	/// Return the number of bytes written to the buffer and the origin of the datagram on success.
+	///
+	/// # Note
+	/// - May assume the buffer is able to hold [`max_datagram_length()`](Self::max_datagram_length) bytes.
+	fn recv_from(&self, buffer: &mut [u8]) -> impl Future<Output = Result<(usize, SocketAddr), TransmitError>> + Send;
+
+	/// Wait for the transmitter to have an incoming datagram, without receiving it.
+	///
+	/// May complete spuriously, in which case the following receive would block.
+	fn readable(&self) -> impl Future<Output = Result<(), IoError>> + Send;
+}
+
+impl AsyncTransmit for UdpSocket {
+	#[inline]
+	fn max_datagram_length(&self) -> usize {
+		DEFAULT_MAX_DATAGRAM_LENGTH
+	}
+
+	#[inline]
+	async fn send_to(&self, data: &[u8], addr: SocketAddr) -> Result<usize, IoError> {
+		UdpSocket::send_to(self, data, addr).await
+	}
+
+	#[inline]
+	async fn recv_from(&self, buffer: &mut [u8]) -> Result<(usize, SocketAddr), TransmitError> {
+		Ok(UdpSocket::recv_from(self, buffer).await?)
+	}
+
+	#[inline]
+	async fn readable(&self) -> Result<(), IoError> {
+		UdpSocket::readable(self).await
+	}
+}
+
+/// Non-blocking implementation, allowing tokio sockets to be used by synchronous code such as
+/// [`ConnectionListener`](crate::connection::listen::ConnectionListener).
+impl Transmit for UdpSocket {
+	#[inline]
+	fn max_datagram_length(&self) -> usize {
+		DEFAULT_MAX_DATAGRAM_LENGTH
+	}
+
+	#[inline]
+	fn send_to(&self, data: &[u8], addr: SocketAddr) -> Result<usize, IoError> {
+		self.try_send_to(data, addr)
+	}
+
+	#[inline]
+	fn try_recv_from(&self, buffer: &mut [u8]) -> Result<(usize, SocketAddr), TransmitError> {
+		Ok(UdpSocket::try_recv_from(self, buffer)?)
+	}
+}
+
+/// # Panics
+/// If called outside of a tokio runtime with IO enabled.
+impl Open for UdpSocket {
+	#[inline]
+	fn open<A: ToSocketAddrs>(addr: A) -> Result<Self, IoError> {
+		Self::from_socket(std::net::UdpSocket::bind(addr)?)
+	}
+}
+
+/// # Panics
+/// If called outside of a tokio runtime with IO enabled.
+impl FromSocket for UdpSocket {
+	#[inline]
+	fn from_socket(socket: std::net::UdpSocket) -> Result<Self, IoError> {
+		socket.set_nonblocking(true)?;
+		UdpSocket::from_std(socket)
+	}
+}
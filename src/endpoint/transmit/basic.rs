@@ -1,34 +1,134 @@
 //! Basic Transmitter implementation.
 
-use crate::endpoint::Open;
+use crate::endpoint::{FromSocket, Open};
 
-use super::{Transmit, TransmitError};
+use super::{Transmit, TransmitError, DEFAULT_MAX_DATAGRAM_LENGTH};
 
-use std::io::Error as IoError;
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 use std::net::{ToSocketAddrs, SocketAddr, UdpSocket};
+use std::time::Duration;
 
 impl Transmit for UdpSocket {
 	#[inline]
 	fn max_datagram_length(&self) -> usize {
-		// Conservative MTU approximation.
-		1200
+		DEFAULT_MAX_DATAGRAM_LENGTH
 	}
 	
 	#[inline]
 	fn send_to(&self, data: &[u8], addr: SocketAddr) -> Result<usize, IoError> {
 		self.send_to(data, addr)
 	}
+
+	#[cfg(all(target_os = "linux", feature = "unix"))]
+	#[inline]
+	fn send_many(&self, datagrams: &[(&[u8], SocketAddr)]) -> Result<usize, IoError> {
+		super::mmsg::send_many(self, datagrams)
+	}
 	
 	#[inline]
 	fn try_recv_from(&self, buffer: &mut [u8]) -> Result<(usize, SocketAddr), TransmitError> {
 		Ok(self.recv_from(buffer)?)
 	}
+
+	#[inline]
+	fn recv_from_timeout(&self, buffer: &mut [u8], timeout: Option<Duration>) -> Result<(usize, SocketAddr), TransmitError> {
+		recv_from_timeout(self, buffer, timeout)
+	}
+}
+
+/// Block on provided socket until a datagram arrives or provided timeout expires.
+///
+/// Waits for the socket to become readable with `poll`, leaving its blocking mode and read
+/// timeout untouched, so both blocking and non-blocking sockets are supported.
+#[cfg(all(unix, feature = "unix"))]
+pub(super) fn recv_from_timeout(
+	socket: &UdpSocket,
+	buffer: &mut [u8],
+	timeout: Option<Duration>,
+) -> Result<(usize, SocketAddr), TransmitError> {
+	use std::os::unix::io::AsRawFd;
+	use std::time::Instant;
+
+	let deadline = timeout.map(|timeout| Instant::now() + timeout);
+	let mut poll_fd = libc::pollfd { fd: socket.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+	loop {
+		let timeout_ms = match deadline {
+			// Round up, so that waiting does not end before the deadline.
+			Some(deadline) => deadline.saturating_duration_since(Instant::now())
+				.as_nanos()
+				.div_ceil(1_000_000)
+				.min(libc::c_int::MAX as u128) as libc::c_int,
+			None => -1,
+		};
+		// SAFETY: a single valid pollfd is passed, its descriptor is owned by the socket.
+		let result = unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) };
+		match result {
+			0 => return Err(TransmitError::NoPendingPackets),
+			result if result > 0 => return Ok(socket.recv_from(buffer)?),
+			_ => {
+				let error = IoError::last_os_error();
+				if error.kind() != IoErrorKind::Interrupted {
+					return Err(error.into())
+				}
+			},
+		}
+	}
+}
+
+/// Block on provided socket until a datagram arrives or provided timeout expires.
+///
+/// Leaves the blocking mode of the socket untouched. Blocking sockets wait using provided read
+/// timeout, which is restored to the previous one afterwards. Non-blocking sockets are polled
+/// every millisecond instead.
+#[cfg(not(all(unix, feature = "unix")))]
+pub(super) fn recv_from_timeout(
+	socket: &UdpSocket,
+	buffer: &mut [u8],
+	timeout: Option<Duration>,
+) -> Result<(usize, SocketAddr), TransmitError> {
+	use std::time::Instant;
+
+	let deadline = timeout.map(|timeout| Instant::now() + timeout);
+	let previous_timeout = socket.read_timeout()?;
+	let result = loop {
+		// Zero read timeout is not a valid socket option, the shortest valid one is used instead.
+		let remaining = deadline.map(|deadline| {
+			deadline.saturating_duration_since(Instant::now()).max(Duration::from_nanos(1))
+		});
+		if let Err(error) = socket.set_read_timeout(remaining) {
+			break Err(error.into())
+		}
+		match socket.recv_from(buffer) {
+			Ok(result) => break Ok(result),
+			// Blocking sockets report expired timeouts as would-block, Windows as timed-out.
+			Err(error) if matches!(error.kind(), IoErrorKind::WouldBlock | IoErrorKind::TimedOut) => {
+				if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+					break Err(TransmitError::NoPendingPackets)
+				}
+				// Non-blocking sockets do not wait on their own.
+				std::thread::sleep(Duration::from_millis(1));
+			},
+			Err(error) => break Err(error.into()),
+		}
+	};
+	socket.set_read_timeout(previous_timeout)?;
+	result
 }
 
 impl Open for UdpSocket {
 	#[inline]
 	fn open<A: ToSocketAddrs>(addr: A) -> Result<Self, IoError> {
-		UdpSocket::bind(addr)
+		let socket = UdpSocket::bind(addr)?;
+		socket.set_nonblocking(true)?;
+		Ok(socket)
+	}
+}
+
+impl FromSocket for UdpSocket {
+	#[inline]
+	fn from_socket(socket: UdpSocket) -> Result<Self, IoError> {
+		socket.set_nonblocking(true)?;
+		Ok(socket)
 	}
 }
 
@@ -43,3 +143,122 @@ fn udp_socket_transmits() {
 
 	super::test::generic_transmit_test((&sender, sender_addr), (&receiver, receiver_addr))
 }
+
+#[cfg(test)]
+#[test]
+fn udp_socket_recv_times_out() {
+	use std::time::Instant;
+
+	let addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10018));
+	let socket = UdpSocket::open(addr).unwrap();
+	let mut buffer = [0; 32];
+
+	let start = Instant::now();
+
+	assert_eq!(
+		socket.recv_from_timeout(&mut buffer, Some(Duration::from_millis(50))),
+		Err(TransmitError::NoPendingPackets),
+	);
+	assert!(start.elapsed() >= Duration::from_millis(50));
+	assert_eq!(socket.read_timeout().unwrap(), None);
+	// The socket must not block after waiting.
+	assert_eq!(socket.try_recv_from(&mut buffer), Err(TransmitError::NoPendingPackets));
+
+	let sender_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10019));
+	let sender = UdpSocket::bind(sender_addr).unwrap();
+	sender.send_to(b"GNET BLOCKING TEST", addr).unwrap();
+
+	assert_eq!(socket.recv_from_timeout(&mut buffer, None), Ok((18, sender_addr)));
+	assert_eq!(&buffer[.. 18], b"GNET BLOCKING TEST");
+}
+
+#[cfg(test)]
+#[test]
+fn blocking_udp_socket_recv_times_out() {
+	use std::time::Instant;
+
+	let addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10025));
+	let socket = UdpSocket::bind(addr).unwrap();
+	let mut buffer = [0; 32];
+
+	let start = Instant::now();
+
+	assert_eq!(
+		Transmit::recv_from_timeout(&socket, &mut buffer, Some(Duration::from_millis(20))),
+		Err(TransmitError::NoPendingPackets),
+	);
+	assert!(start.elapsed() >= Duration::from_millis(20));
+	assert_eq!(
+		Transmit::recv_from_timeout(&socket, &mut buffer, Some(Duration::ZERO)),
+		Err(TransmitError::NoPendingPackets),
+	);
+	assert_eq!(socket.read_timeout().unwrap(), None);
+
+	// The socket must remain blocking, waiting for its own read timeout.
+	socket.set_read_timeout(Some(Duration::from_millis(20))).unwrap();
+	let start = Instant::now();
+
+	assert!(socket.recv_from(&mut buffer).is_err());
+	assert!(start.elapsed() >= Duration::from_millis(10), "The socket must remain blocking!");
+
+	let sender_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10026));
+	let sender = UdpSocket::bind(sender_addr).unwrap();
+	sender.send_to(b"GNET", addr).unwrap();
+
+	assert_eq!(Transmit::recv_from_timeout(&socket, &mut buffer, Some(Duration::from_secs(1))), Ok((4, sender_addr)));
+	assert_eq!(socket.read_timeout().unwrap(), Some(Duration::from_millis(20)));
+}
+
+#[cfg(test)]
+#[test]
+fn udp_socket_sends_many() {
+	let sender_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10020));
+	let sender = UdpSocket::open(sender_addr).unwrap();
+	let receiver_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10021));
+	let receiver = UdpSocket::open(receiver_addr).unwrap();
+
+	let datagrams: [(&[u8], _); 3] = [
+		(b"GNET FIRST", receiver_addr),
+		(b"GNET SECOND", receiver_addr),
+		(b"GNET THIRD", receiver_addr),
+	];
+
+	assert_eq!(sender.send_many(&datagrams).unwrap(), 3);
+	assert_eq!(sender.send_many(&[]).unwrap(), 0);
+
+	let mut buffer = [0; 32];
+	let mut received = Vec::new();
+	while received.len() < datagrams.len() {
+		let (len, src) = receiver.recv_from_timeout(&mut buffer, Some(Duration::from_secs(1))).unwrap();
+		assert_eq!(src, sender_addr);
+		received.push(buffer[.. len].to_vec());
+	}
+	received.sort();
+
+	assert_eq!(received, [b"GNET FIRST".to_vec(), b"GNET SECOND".to_vec(), b"GNET THIRD".to_vec()]);
+}
+
+#[cfg(test)]
+#[test]
+fn udp_socket_from_bound_socket_transmits() {
+	let sender_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10008));
+	let sender = UdpSocket::bind(sender_addr).unwrap();
+
+	let receiver_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10009));
+	let receiver = UdpSocket::from_socket(UdpSocket::bind(receiver_addr).unwrap()).unwrap();
+
+	assert_eq!(receiver.local_addr().unwrap(), receiver_addr);
+
+	sender.send_to(b"GNET FROM SOCKET TEST", receiver_addr).unwrap();
+
+	let mut buffer = [0; 32];
+	let received = loop {
+		match receiver.try_recv_from(&mut buffer) {
+			Err(TransmitError::NoPendingPackets) => std::thread::yield_now(),
+			result => break result,
+		}
+	};
+
+	assert_eq!(received, Ok((21, sender_addr)));
+	assert_eq!(&buffer[.. 21], b"GNET FROM SOCKET TEST");
+}
@@ -0,0 +1,69 @@
+//! Batched sending of datagrams with a single `sendmmsg` system call.
+//!
+//! Requires the `unix` feature.
+
+use std::io::Error as IoError;
+use std::mem::{size_of, zeroed};
+use std::net::{SocketAddr, UdpSocket};
+use std::os::unix::io::AsRawFd;
+
+/// Send provided datagrams through provided socket, returning the number of sent ones.
+///
+/// Stops at the first failed datagram, which is only reported if no datagrams were sent.
+pub(super) fn send_many(socket: &UdpSocket, datagrams: &[(&[u8], SocketAddr)]) -> Result<usize, IoError> {
+	let mut addrs: Vec<_> = datagrams.iter().map(|&(_, addr)| to_sockaddr(addr)).collect();
+	let mut iovecs: Vec<_> = datagrams.iter()
+		.map(|&(data, _)| libc::iovec { iov_base: data.as_ptr() as *mut libc::c_void, iov_len: data.len() })
+		.collect();
+	let mut messages: Vec<_> = addrs.iter_mut().zip(iovecs.iter_mut())
+		.map(|((addr, addr_len), iovec)| {
+			// SAFETY: mmsghdr is a plain C struct, for which zero is a valid value.
+			let mut message: libc::mmsghdr = unsafe { zeroed() };
+			message.msg_hdr.msg_name = addr as *mut _ as *mut libc::c_void;
+			message.msg_hdr.msg_namelen = *addr_len;
+			message.msg_hdr.msg_iov = iovec;
+			message.msg_hdr.msg_iovlen = 1;
+			message
+		})
+		.collect();
+
+	let mut sent = 0;
+	while sent < messages.len() {
+		let remaining = &mut messages[sent ..];
+		// SAFETY: the messages point to addresses and data that outlive the call.
+		let result = unsafe {
+			libc::sendmmsg(socket.as_raw_fd(), remaining.as_mut_ptr(), remaining.len() as libc::c_uint, 0)
+		};
+		if result < 0 {
+			let error = IoError::last_os_error();
+			return if sent == 0 { Err(error) } else { Ok(sent) }
+		}
+		sent += result as usize;
+	}
+	Ok(sent)
+}
+
+fn to_sockaddr(addr: SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+	// SAFETY: sockaddr_storage is a plain C struct, for which zero is a valid value.
+	let mut storage: libc::sockaddr_storage = unsafe { zeroed() };
+	match addr {
+		SocketAddr::V4(addr) => {
+			// SAFETY: sockaddr_storage is large and aligned enough to hold any socket address.
+			let sockaddr = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in) };
+			sockaddr.sin_family = libc::AF_INET as libc::sa_family_t;
+			sockaddr.sin_port = addr.port().to_be();
+			sockaddr.sin_addr.s_addr = u32::from_ne_bytes(addr.ip().octets());
+			(storage, size_of::<libc::sockaddr_in>() as libc::socklen_t)
+		},
+		SocketAddr::V6(addr) => {
+			// SAFETY: sockaddr_storage is large and aligned enough to hold any socket address.
+			let sockaddr = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in6) };
+			sockaddr.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+			sockaddr.sin6_port = addr.port().to_be();
+			sockaddr.sin6_flowinfo = addr.flowinfo();
+			sockaddr.sin6_addr.s6_addr = addr.ip().octets();
+			sockaddr.sin6_scope_id = addr.scope_id();
+			(storage, size_of::<libc::sockaddr_in6>() as libc::socklen_t)
+		},
+	}
+}
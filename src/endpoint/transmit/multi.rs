@@ -0,0 +1,181 @@
+//! Transmitter that merges multiple transmitters into one logical endpoint.
+
+use super::{EndpointStats, Transmit, TransmitError};
+
+use std::cell::Cell;
+use std::io::Error as IoError;
+use std::net::SocketAddr;
+
+/// The rule [`MultiTransmit`](MultiTransmit) uses to pick a transmitter for sending a datagram.
+#[derive(Debug, Clone, Copy)]
+pub enum Routing {
+	/// Cycle through the transmitters in order, one per sent datagram.
+	RoundRobin,
+	/// Use the transmitter with the index returned by provided function for the destination.
+	///
+	/// The returned index wraps around the number of transmitters.
+	ByDestination(fn(SocketAddr) -> usize),
+}
+
+/// A transmitter that merges several transmitters behind one logical endpoint.
+///
+/// Useful for servers that listen on multiple interfaces or sockets at once. Received datagrams
+/// are collected from each transmitter in turn, while sent ones are routed according to the
+/// provided [`Routing`](Routing).
+#[derive(Debug)]
+pub struct MultiTransmit<T: Transmit> {
+	transmitters: Vec<T>,
+	routing: Routing,
+	next_sender: Cell<usize>,
+	next_receiver: Cell<usize>,
+}
+
+impl<T: Transmit> MultiTransmit<T> {
+	/// Merge provided transmitters, sending datagrams in a round-robin fashion.
+	///
+	/// # Panics
+	/// If no transmitters are provided.
+	#[inline]
+	pub fn new(transmitters: Vec<T>) -> Self {
+		Self::with_routing(transmitters, Routing::RoundRobin)
+	}
+
+	/// Merge provided transmitters, sending datagrams according to provided routing rule.
+	///
+	/// # Panics
+	/// If no transmitters are provided.
+	pub fn with_routing(transmitters: Vec<T>, routing: Routing) -> Self {
+		assert!(!transmitters.is_empty(), "MultiTransmit requires at least one transmitter!");
+		Self {
+			transmitters,
+			routing,
+			next_sender: Cell::new(0),
+			next_receiver: Cell::new(0),
+		}
+	}
+
+	/// Get the merged transmitters.
+	#[inline]
+	pub fn transmitters(&self) -> &[T] {
+		&self.transmitters
+	}
+
+	/// Split the merged endpoint back into its transmitters.
+	#[inline]
+	pub fn into_transmitters(self) -> Vec<T> {
+		self.transmitters
+	}
+
+	/// Pick the index of the transmitter to send a datagram to provided address with.
+	fn route(&self, addr: SocketAddr) -> usize {
+		match self.routing {
+			Routing::RoundRobin => {
+				let index = self.next_sender.get();
+				self.next_sender.set((index + 1) % self.transmitters.len());
+				index
+			},
+			Routing::ByDestination(route) => route(addr) % self.transmitters.len(),
+		}
+	}
+}
+
+impl<T: Transmit> Transmit for MultiTransmit<T> {
+	#[inline]
+	fn max_datagram_length(&self) -> usize {
+		// Any of the transmitters may be used to send a datagram.
+		self.transmitters.iter().map(Transmit::max_datagram_length).min().unwrap()
+	}
+
+	#[inline]
+	fn send_to(&self, data: &[u8], addr: SocketAddr) -> Result<usize, IoError> {
+		self.transmitters[self.route(addr)].send_to(data, addr)
+	}
+
+	fn try_recv_from(&self, buffer: &mut [u8]) -> Result<(usize, SocketAddr), TransmitError> {
+		// Start polling with a different transmitter each time, so a busy one does not starve others.
+		let first = self.next_receiver.get();
+		self.next_receiver.set((first + 1) % self.transmitters.len());
+		for offset in 0 .. self.transmitters.len() {
+			let index = (first + offset) % self.transmitters.len();
+			match self.transmitters[index].try_recv_from(buffer) {
+				Err(TransmitError::NoPendingPackets) => continue,
+				result => return result,
+			}
+		}
+		Err(TransmitError::NoPendingPackets)
+	}
+
+	fn stats(&self) -> EndpointStats {
+		let mut stats = EndpointStats::default();
+		for transmitter in &self.transmitters {
+			stats += transmitter.stats();
+		}
+		stats
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::endpoint::Open;
+
+	use std::net::UdpSocket;
+	use std::thread::sleep;
+	use std::time::Duration;
+
+	#[test]
+	fn multi_transmit_receives_from_all() {
+		let first_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10002));
+		let second_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10003));
+		let merged = MultiTransmit::new(vec![
+			UdpSocket::open(first_addr).unwrap(),
+			UdpSocket::open(second_addr).unwrap(),
+		]);
+
+		let sender_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10004));
+		let sender = UdpSocket::bind(sender_addr).unwrap();
+		sender.send_to(b"FIRST", first_addr).unwrap();
+		sender.send_to(b"SECOND", second_addr).unwrap();
+
+		let mut buffer = vec![0; merged.max_datagram_length()];
+		let mut received = Vec::new();
+		for _ in 0 .. 100 {
+			match merged.try_recv_from(&mut buffer) {
+				Ok((len, src)) => {
+					assert_eq!(src, sender_addr);
+					received.push(buffer[.. len].to_vec());
+					if received.len() == 2 {
+						break
+					}
+				},
+				Err(TransmitError::NoPendingPackets) => sleep(Duration::from_millis(1)),
+				Err(error) => panic!("{}", error),
+			}
+		}
+		received.sort();
+
+		assert_eq!(received, [b"FIRST".to_vec(), b"SECOND".to_vec()]);
+		assert_eq!(merged.try_recv_from(&mut buffer), Err(TransmitError::NoPendingPackets));
+	}
+
+	#[test]
+	fn multi_transmit_routes_by_destination() {
+		let first_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10005));
+		let second_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10006));
+		let merged = MultiTransmit::with_routing(
+			vec![
+				UdpSocket::open(first_addr).unwrap(),
+				UdpSocket::open(second_addr).unwrap(),
+			],
+			Routing::ByDestination(|addr| addr.port() as usize),
+		);
+
+		let receiver_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 10007));
+		let receiver = UdpSocket::bind(receiver_addr).unwrap();
+		merged.send_to(b"ROUTED", receiver_addr).unwrap();
+
+		let mut buffer = [0; 16];
+		// Port 10007 is odd, so the datagram must be sent from the second socket.
+		assert_eq!(receiver.recv_from(&mut buffer).unwrap(), (6, second_addr));
+	}
+}
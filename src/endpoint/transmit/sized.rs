@@ -0,0 +1,158 @@
+//! Transmitter with a maximum datagram length chosen at runtime.
+
+use crate::endpoint::{FromSocket, Open};
+
+use super::{Transmit, TransmitError, DEFAULT_MAX_DATAGRAM_LENGTH};
+
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use std::net::{ToSocketAddrs, SocketAddr, UdpSocket};
+use std::time::Duration;
+
+/// Largest possible payload of a UDP datagram over IPv4.
+pub const MAX_UDP_DATAGRAM_LENGTH: usize = 65507;
+
+/// A [`UdpSocket`](UdpSocket) with a configurable maximum datagram length.
+///
+/// [`UdpSocket`](UdpSocket) transmitter conservatively assumes datagrams of up to
+/// [`DEFAULT_MAX_DATAGRAM_LENGTH`](DEFAULT_MAX_DATAGRAM_LENGTH) bytes. Local networks supporting
+/// jumbo frames may use longer datagrams, while some tunnels require shorter ones.
+///
+/// **NOTE**: both ends of a connection should use the same length, see
+/// [`validate_compatibility()`](super::validate_compatibility).
+#[derive(Debug)]
+pub struct SizedSocket {
+	socket: UdpSocket,
+	max_datagram_length: usize,
+}
+
+impl SizedSocket {
+	/// Attempt to open a new socket bound to provided address, that sends datagrams of up to
+	/// provided length.
+	///
+	/// Fails with [`InvalidInput`](IoErrorKind::InvalidInput) if the length is `0` or greater
+	/// than [`MAX_UDP_DATAGRAM_LENGTH`](MAX_UDP_DATAGRAM_LENGTH).
+	pub fn open_with_max_datagram_length<A: ToSocketAddrs>(addr: A, max_datagram_length: usize) -> Result<Self, IoError> {
+		Self::from_socket_with_max_datagram_length(UdpSocket::bind(addr)?, max_datagram_length)
+	}
+
+	/// Attempt to construct a new transmitter using provided bound socket, that sends datagrams of
+	/// up to provided length.
+	///
+	/// Fails with [`InvalidInput`](IoErrorKind::InvalidInput) if the length is `0` or greater
+	/// than [`MAX_UDP_DATAGRAM_LENGTH`](MAX_UDP_DATAGRAM_LENGTH).
+	pub fn from_socket_with_max_datagram_length(socket: UdpSocket, max_datagram_length: usize) -> Result<Self, IoError> {
+		if max_datagram_length == 0 || max_datagram_length > MAX_UDP_DATAGRAM_LENGTH {
+			return Err(IoError::new(IoErrorKind::InvalidInput, "unsupported maximum datagram length"))
+		}
+		socket.set_nonblocking(true)?;
+		Ok(Self { socket, max_datagram_length })
+	}
+
+	/// Get the underlying socket.
+	#[inline]
+	pub fn socket(&self) -> &UdpSocket {
+		&self.socket
+	}
+
+	/// Split the transmitter into the underlying socket.
+	#[inline]
+	pub fn into_socket(self) -> UdpSocket {
+		self.socket
+	}
+}
+
+impl Transmit for SizedSocket {
+	#[inline]
+	fn max_datagram_length(&self) -> usize {
+		self.max_datagram_length
+	}
+
+	#[inline]
+	fn send_to(&self, data: &[u8], addr: SocketAddr) -> Result<usize, IoError> {
+		self.socket.send_to(data, addr)
+	}
+
+	#[inline]
+	fn send_many(&self, datagrams: &[(&[u8], SocketAddr)]) -> Result<usize, IoError> {
+		self.socket.send_many(datagrams)
+	}
+
+	#[inline]
+	fn try_recv_from(&self, buffer: &mut [u8]) -> Result<(usize, SocketAddr), TransmitError> {
+		Ok(self.socket.recv_from(buffer)?)
+	}
+
+	#[inline]
+	fn recv_from_timeout(&self, buffer: &mut [u8], timeout: Option<Duration>) -> Result<(usize, SocketAddr), TransmitError> {
+		super::basic::recv_from_timeout(&self.socket, buffer, timeout)
+	}
+}
+
+impl Open for SizedSocket {
+	#[inline]
+	fn open<A: ToSocketAddrs>(addr: A) -> Result<Self, IoError> {
+		Self::open_with_max_datagram_length(addr, DEFAULT_MAX_DATAGRAM_LENGTH)
+	}
+}
+
+impl FromSocket for SizedSocket {
+	#[inline]
+	fn from_socket(socket: UdpSocket) -> Result<Self, IoError> {
+		Self::from_socket_with_max_datagram_length(socket, DEFAULT_MAX_DATAGRAM_LENGTH)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	use std::thread::sleep;
+
+	fn assert_full_datagram_round_trips(max_datagram_length: usize, sender_port: u16, receiver_port: u16) {
+		let sender_addr = SocketAddr::from(([ 127, 0, 0, 1, ], sender_port));
+		let sender = SizedSocket::open_with_max_datagram_length(sender_addr, max_datagram_length).unwrap();
+		let receiver_addr = SocketAddr::from(([ 127, 0, 0, 1, ], receiver_port));
+		let receiver = SizedSocket::open_with_max_datagram_length(receiver_addr, max_datagram_length).unwrap();
+
+		assert_eq!(super::super::validate_compatibility(&sender, &receiver), Ok(()));
+
+		let datagram: Vec<_> = (0 .. sender.max_datagram_length()).map(|i| i as u8).collect();
+		assert_eq!(sender.send_to(&datagram, receiver_addr).unwrap(), max_datagram_length);
+
+		let mut buffer = vec![0; receiver.max_datagram_length()];
+		let received = loop {
+			match receiver.try_recv_from(&mut buffer) {
+				Err(TransmitError::NoPendingPackets) => sleep(Duration::from_millis(1)),
+				result => break result,
+			}
+		};
+
+		assert_eq!(received, Ok((max_datagram_length, sender_addr)));
+		assert_eq!(buffer, datagram);
+	}
+
+	#[test]
+	fn short_datagrams_round_trip() {
+		assert_full_datagram_round_trips(512, 10014, 10015);
+	}
+
+	#[test]
+	fn long_datagrams_round_trip() {
+		assert_full_datagram_round_trips(1500, 10016, 10017);
+	}
+
+	#[test]
+	fn invalid_length_is_rejected() {
+		let addr = SocketAddr::from(([ 127, 0, 0, 1, ], 0));
+
+		assert_eq!(
+			SizedSocket::open_with_max_datagram_length(addr, 0).unwrap_err().kind(),
+			IoErrorKind::InvalidInput,
+		);
+		assert_eq!(
+			SizedSocket::open_with_max_datagram_length(addr, MAX_UDP_DATAGRAM_LENGTH + 1).unwrap_err().kind(),
+			IoErrorKind::InvalidInput,
+		);
+		assert_eq!(SizedSocket::open(addr).unwrap().max_datagram_length(), DEFAULT_MAX_DATAGRAM_LENGTH);
+	}
+}
@@ -1,9 +1,10 @@
 //! Generic testing functions for [`Transmit`](Transmit) implementations.
 
-use super::Transmit;
+use super::{DatagramLengthMismatch, Transmit, TransmitError, validate_compatibility};
 
 use std::cmp::max;
-use std::net::SocketAddr;
+use std::io::Error as IoError;
+use std::net::{SocketAddr, UdpSocket};
 
 const DATAGRAMS: [&[u8]; 3] = [
 	b"GNET TRANSMIT TEST FIRST DATAGRAM",
@@ -45,3 +46,31 @@ pub fn generic_transmit_test<S: Transmit, R: Transmit>(
 		assert_eq!(&buffer[.. DATAGRAMS[0].len()], DATAGRAMS[0]);
 	}
 }
+
+/// Transmitter with a configurable maximum datagram length, that never transmits anything.
+struct SilentTransmit(usize);
+
+impl Transmit for SilentTransmit {
+	fn max_datagram_length(&self) -> usize {
+		self.0
+	}
+
+	fn send_to(&self, data: &[u8], _: SocketAddr) -> Result<usize, IoError> {
+		Ok(data.len())
+	}
+
+	fn try_recv_from(&self, _: &mut [u8]) -> Result<(usize, SocketAddr), TransmitError> {
+		Err(TransmitError::NoPendingPackets)
+	}
+}
+
+#[test]
+fn mismatched_datagram_lengths_are_reported() {
+	let socket = UdpSocket::bind(SocketAddr::from(([ 127, 0, 0, 1, ], 10010))).unwrap();
+
+	assert_eq!(validate_compatibility(&socket, &SilentTransmit(socket.max_datagram_length())), Ok(()));
+	assert_eq!(
+		validate_compatibility(&socket, &SilentTransmit(600)),
+		Err(DatagramLengthMismatch { first: socket.max_datagram_length(), second: 600 }),
+	);
+}
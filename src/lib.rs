@@ -9,13 +9,25 @@
 //! ## Features
 //!
 //! - `protocol` (default) - enables the [`protocol`](protocol) module. Users may opt-out if
-//! they with to only use endpoint or byte-serialization portions of the library.
+//!   they with to only use endpoint or byte-serialization portions of the library.
 //! - `adv-endpoint` - advanced endpoint implementations. Their use is encouraged over using
-//! default library [`endpoint`](endpoint) trait implementors, as the focus was simplicity
-//! instead of performance.
+//!   default library [`endpoint`](endpoint) trait implementors, as the focus was simplicity
+//!   instead of performance.
+//! - `bitflags` - enables implementing [`ByteSerialize`](byte::ByteSerialize) for types
+//!   generated by the `bitflags` crate.
+//! - `derive` - enables `#[derive(ByteSerialize)]` for structs and enums, see
+//!   [`ByteSerialize`](byte::ByteSerialize).
+//! - `unix` - enables configuring socket options that are not exposed by the standard library,
+//!   such as the traffic class of sent datagrams, and batched sending with `sendmmsg` on Linux.
+//!   Only available on unix platforms.
+//! - `serde` - enables using types implementing `serde` traits as parcels through
+//!   `SerdeParcel`, see [`ByteSerialize`](byte::ByteSerialize).
+//! - `tokio` - enables awaiting datagrams and connection requests on tokio sockets, see
+//!   [`AsyncTransmit`](endpoint::AsyncTransmit).
+//! - `proptest` - enables property-based tests of [byte](byte)-serialization implementations.
 
 #![warn(clippy::all)]
 
 pub mod byte;
-// pub mod endpoint;
+pub mod endpoint;
 pub mod connection;
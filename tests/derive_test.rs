@@ -0,0 +1,102 @@
+#![cfg(feature = "derive")]
+
+use gnet::byte::{ByteSerialize, SerializationError};
+
+use std::fmt::Debug;
+
+#[derive(ByteSerialize, Debug, PartialEq)]
+struct Transform {
+	position: [f32; 3],
+	scale: f32,
+}
+
+#[derive(ByteSerialize, Debug, PartialEq)]
+struct Entity {
+	id: u32,
+	velocity: [f32; 3],
+	transform: Transform,
+}
+
+#[derive(ByteSerialize, Debug, PartialEq)]
+struct Marker;
+
+#[derive(ByteSerialize, Debug, PartialEq)]
+struct Pair<T>(T, T);
+
+#[derive(ByteSerialize, Debug, PartialEq)]
+enum Command {
+	Stop,
+	Move([f32; 3]),
+	Spawn { entity: Entity, name: String },
+}
+
+/// Fields named like the locals of the generated code.
+#[derive(ByteSerialize, Debug, PartialEq)]
+struct Blob {
+	bytes: Vec<u8>,
+	offset: u32,
+	byte_count: u16,
+}
+
+#[derive(ByteSerialize, Debug, PartialEq)]
+enum Chunk {
+	Blob { bytes: Vec<u8>, offset: u32, discriminant: u8 },
+}
+
+fn assert_round_trip<T: ByteSerialize + PartialEq + Debug>(value: &T) {
+	let mut bytes = vec![0; value.byte_count()];
+	value.to_bytes(&mut bytes);
+
+	let (deserialized, byte_count) = T::from_bytes(&bytes).unwrap();
+
+	assert_eq!(byte_count, bytes.len());
+	assert_eq!(&deserialized, value);
+}
+
+fn entity() -> Entity {
+	Entity {
+		id: 0xDEAD_BEEF,
+		velocity: [1.0, -2.0, 0.5],
+		transform: Transform { position: [3.0, 4.0, 5.0], scale: 2.0 },
+	}
+}
+
+#[test]
+fn derived_struct_round_trips() {
+	let entity = entity();
+
+	assert_eq!(entity.byte_count(), 4 + 12 + 12 + 4);
+	assert_round_trip(&entity);
+	assert_round_trip(&Pair(1u16, 2));
+	assert_round_trip(&(Marker, 7u8));
+	assert_eq!(Marker.byte_count(), 0);
+	assert_eq!(Marker::from_bytes(&[]), Ok((Marker, 0)));
+}
+
+#[test]
+fn derived_struct_serializes_fields_in_order() {
+	let mut bytes = [0; 6];
+	Pair(0x0102u16, 0x0304).to_bytes(&mut bytes);
+
+	assert_eq!(bytes[.. 4], [0x02, 0x01, 0x04, 0x03]);
+}
+
+#[test]
+fn derived_enum_round_trips() {
+	assert_round_trip(&Command::Stop);
+	assert_round_trip(&Command::Move([0.0, 1.0, 2.0]));
+	assert_round_trip(&Command::Spawn { entity: entity(), name: "GNET".to_string() });
+
+	let mut bytes = [0; 13];
+	Command::Move([0.0; 3]).to_bytes(&mut bytes);
+
+	assert_eq!(bytes[0], 1);
+	assert_eq!(Command::from_bytes(&[3]), Err(SerializationError::UnexpectedValue));
+	assert_eq!(Command::from_bytes(&bytes[.. 12]), Err(SerializationError::BufferOverflow));
+}
+
+#[test]
+fn derived_fields_may_share_names_with_generated_locals() {
+	assert_round_trip(&Blob { bytes: vec![1, 2, 3], offset: 4, byte_count: 5 });
+	assert_round_trip(&Chunk::Blob { bytes: vec![6], offset: 7, discriminant: 8 });
+}
@@ -0,0 +1,69 @@
+use gnet::connection::context::{Context, ConnectionStatus};
+use gnet::connection::packet;
+use gnet::endpoint::{Open, Transmit};
+
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+struct Idle(u32);
+
+gnet::byte_serialize_newtype!(Idle(u32));
+
+impl gnet::connection::Parcel for Idle {}
+
+/// Build the packets due at provided time and send them to provided address.
+///
+/// Returns the number of sent packets.
+fn send_due_packets(context: &mut Context<Idle>, socket: &UdpSocket, addr: SocketAddr, now: Instant) -> usize {
+	let mut buffer = [0; 1200];
+	let mut count = 0;
+	while let Some(len) = context.poll_packet(now, &mut buffer).unwrap() {
+		assert!(packet::read_header(&buffer[.. len]).signal.is_empty());
+		socket.send_to(&buffer[.. len], addr).unwrap();
+		count += 1;
+	}
+	count
+}
+
+fn recv_packets(context: &mut Context<Idle>, socket: &UdpSocket, count: usize) {
+	let mut buffer = [0; 1200];
+	for _ in 0 .. count {
+		let (len, _) = socket.recv_from_timeout(&mut buffer, Some(Duration::from_secs(1))).unwrap();
+		context.ingest(&buffer[.. len]).unwrap();
+	}
+}
+
+#[test]
+fn idle_connections_are_kept_alive() {
+	let first_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 2102));
+	let second_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 2103));
+	let first_socket = UdpSocket::open(first_addr).unwrap();
+	let second_socket = UdpSocket::open(second_addr).unwrap();
+
+	let mut first = Context::<Idle>::accept(1);
+	let mut second = Context::<Idle>::accept(1);
+	for context in [ &mut first, &mut second, ] {
+		context.set_timeout(Duration::from_secs(2)).unwrap();
+		context.set_keep_alive_interval(Duration::from_millis(500)).unwrap();
+	}
+
+	let start = Instant::now();
+	let mut keep_alive_count = 0;
+	// Simulate 5 seconds of idle connections, which is longer than their timeouts.
+	for step in 0 ..= 20 {
+		let now = start + Duration::from_millis(250) * step;
+		first.update(now).unwrap();
+		second.update(now).unwrap();
+
+		let first_sent = send_due_packets(&mut first, &first_socket, second_addr, now);
+		let second_sent = send_due_packets(&mut second, &second_socket, first_addr, now);
+		recv_packets(&mut second, &second_socket, first_sent);
+		recv_packets(&mut first, &first_socket, second_sent);
+		keep_alive_count += first_sent + second_sent;
+	}
+
+	assert_eq!(first.status(), ConnectionStatus::Open);
+	assert_eq!(second.status(), ConnectionStatus::Open);
+	// Each end sends a keep-alive every other step.
+	assert_eq!(keep_alive_count, 20);
+}
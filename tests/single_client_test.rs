@@ -1,6 +1,8 @@
+mod support;
+
 use gnet::byte::ByteSerialize;
-use gnet::connection::context::Context;
-use gnet::connection::packet;
+use gnet::connection::listen::ConnectionListener;
+use gnet::endpoint::Transmit;
 use std::net::{SocketAddr, UdpSocket};
 
 #[derive(Debug, PartialEq, Clone)]
@@ -24,7 +26,7 @@ impl ByteSerialize for TestParcel {
 				let byte_count = string.len() as u16;
 				byte_count.to_bytes(&mut bytes[1 ..]);
 				let bytes = &mut bytes[byte_count.byte_count() + 1 ..];
-				bytes.copy_from_slice(string.as_bytes());
+				bytes[.. string.len()].copy_from_slice(string.as_bytes());
 			},
 			Self::Index(value) => {
 				1u8.to_bytes(bytes);
@@ -38,7 +40,10 @@ impl ByteSerialize for TestParcel {
 		match variant_index {
 			0 => {
 				let (byte_count, extra_offset) = u16::from_bytes(&bytes[offset ..])?;
-				let string = String::from_utf8(bytes[offset .. offset + byte_count as usize].to_vec())?;
+				let string_start = offset + extra_offset;
+				let string_bytes = bytes.get(string_start .. string_start + byte_count as usize)
+					.ok_or(gnet::byte::SerializationError::BufferOverflow)?;
+				let string = String::from_utf8(string_bytes.to_vec())?;
 				Ok((Self::String(string), offset + extra_offset + byte_count as usize))
 			},
 			1 => {
@@ -56,31 +61,55 @@ impl gnet::connection::Parcel for TestParcel {}
 fn single_client_test() {
 	const REQUEST_PAYLOAD: &[u8] = b"Single Client Test Connection Request";
 
-	let mut byte_buffer = vec![0; 1200].into_boxed_slice();
-
-	let test_parcel = TestParcel::String("Hello there friend!".to_string());
+	let client_parcel = TestParcel::String("Hello there friend!".to_string());
+	let server_parcel = TestParcel::Index(42);
+	let mut byte_buffer = vec![0; 1200];
 
 	let listener_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 2100));
 	let client_addr = SocketAddr::from(([ 127, 0, 0, 1, ], 2101));
 
 	// Set up listener
-	// TODO:
-	let listener_socket = UdpSocket::bind(listener_addr).expect("Faild to bind listener socket.");
-	// Listener::new()
-	
+	let mut listener = ConnectionListener::<_, TestParcel>::bind(listener_addr)
+		.expect("Faild to bind listener socket.");
+
 	// Set up client
 	let client_socket = UdpSocket::bind(client_addr).expect("Failed to bind client socket");
-	let mut client_context = Context::<TestParcel>::pending();
 
 	// Connect
-	let len = client_context.build_request_packet(&mut byte_buffer, REQUEST_PAYLOAD).unwrap();
+	let (mut server_context, mut client_context) = support::handshake_roundtrip(
+		&mut listener,
+		listener_addr,
+		&client_socket,
+		REQUEST_PAYLOAD,
+	);
+
+	let connection_id = server_context.connection_id().unwrap();
+
+	// Client to server
+	client_context.push_volatile_parcel(client_parcel.clone()).unwrap();
+	let len = client_context.build_packet(&mut byte_buffer).unwrap();
 	client_socket.send_to(&byte_buffer[.. len], listener_addr).unwrap();
-	
-	// Accept
-	let (recv_bytes, recv_addr) = listener_socket.recv_from(&mut byte_buffer).unwrap();
-	assert_eq!(recv_addr, client_addr);
-	// TODO: listener utility that allows constructing accept packet
-	assert_eq!(packet::get_data_segment(&byte_buffer[.. recv_bytes]), REQUEST_PAYLOAD);
-
-	// TODO: send and receive parcels from both ends
+
+	let received = loop {
+		listener.recv().expect("Failed to receive on the listener.");
+		listener.process(connection_id, |(packet, addr)| {
+			assert_eq!(addr, client_addr);
+			server_context.ingest(packet).expect("Failed to process the client packet.");
+		});
+		if let Some((parcel, _)) = server_context.try_recv().unwrap() {
+			break parcel
+		}
+		std::thread::yield_now();
+	};
+	assert_eq!(received, client_parcel);
+
+	// Server to client
+	server_context.push_volatile_parcel(server_parcel.clone()).unwrap();
+	let len = server_context.build_packet(&mut byte_buffer).unwrap();
+	listener.endpoint().send_to(&byte_buffer[.. len], client_addr).unwrap();
+
+	let (len, recv_addr) = client_socket.recv_from(&mut byte_buffer).unwrap();
+	assert_eq!(recv_addr, listener_addr);
+	client_context.ingest(&byte_buffer[.. len]).expect("Failed to process the server packet.");
+	assert_eq!(client_context.pop_parcel().unwrap().0, server_parcel);
 }
@@ -0,0 +1,61 @@
+//! Shared support of integration tests.
+
+use gnet::connection::Parcel;
+use gnet::connection::context::{Context, ConnectionStatus};
+use gnet::connection::id::ConnectionId;
+use gnet::connection::listen::{AcceptDecision, AcceptError, ConnectionListener};
+use gnet::endpoint::{Demux, Transmit};
+
+use std::net::{SocketAddr, UdpSocket};
+
+/// Establish a connection between provided listener and client socket.
+///
+/// Sends a connection request with provided payload from the blocking client socket, accepts
+/// it on the listener and processes the response on the client. Returns the established server
+/// and client contexts, after asserting that both are open and share the connection id.
+pub fn handshake_roundtrip<E, P>(
+	listener: &mut ConnectionListener<E, P>,
+	listener_addr: SocketAddr,
+	client: &UdpSocket,
+	payload: &[u8],
+) -> (Context<P>, Context<P>)
+where
+	E: Transmit + Demux<ConnectionId>,
+	P: Parcel,
+{
+	let client_addr = client.local_addr().unwrap();
+	let mut buffer = vec![0; 1200];
+
+	// Request
+	let mut client_context = Context::<P>::pending();
+	let len = client_context.build_request_packet(&mut buffer, payload).unwrap();
+	client.send_to(&buffer[.. len], listener_addr).unwrap();
+
+	// Accept
+	let server_context = loop {
+		let predicate = |addr, request: &[u8]| if addr == client_addr && request == payload {
+			AcceptDecision::Allow(Vec::new())
+		} else {
+			AcceptDecision::Reject
+		};
+		match listener.try_accept(predicate) {
+			Ok((context, addr)) => {
+				assert_eq!(addr, client_addr);
+				break context
+			},
+			Err(AcceptError::NoPendingConnections) => std::thread::yield_now(),
+			Err(error) => panic!("Failed to accept the client connection: {}", error),
+		}
+	};
+
+	// Establish
+	let (len, recv_addr) = client.recv_from(&mut buffer).unwrap();
+	assert_eq!(recv_addr, listener_addr);
+	client_context.ingest_response(&buffer[.. len]).expect("Failed to process the accepting response.");
+
+	assert_eq!(server_context.status(), ConnectionStatus::Open);
+	assert_eq!(client_context.status(), ConnectionStatus::Open);
+	assert_eq!(client_context.connection_id(), server_context.connection_id());
+
+	(server_context, client_context)
+}